@@ -1,75 +1,161 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use std::thread;
+use std::time::Duration;
 
 use eframe::epaint::Color32;
 use egui::{Align2, Pos2, Rect, RichText, Vec2};
 use image::io::Reader;
+use image_converter::{
+    convert_with_progress, plan, CacheMode, CancellationToken, CollisionPolicy, ConversionPlan,
+    ConversionReport, ConvertEvent, ConvertOptions, DdsRule, ErrorPolicy, FileStatus,
+    FolderWatcher, ImageFormatEnum, ImageScanner, MipmapPolicy, OutputAction, OverwritePolicy,
+    PlannedAction, Preset, Presets, Progress, ScanEvent, ScanOptions, WatchEvent, WebpCompression,
+    DEFAULT_DEBOUNCE, IGNORE_FILE_NAME,
+};
 use log::{debug, error, info};
-use strum::{EnumString, IntoEnumIterator, IntoStaticStr};
-use walkdir::WalkDir;
-
-use crate::image_converter::convert;
-
-#[derive(Debug, PartialEq, Clone, EnumString, IntoStaticStr, Copy)]
-pub enum ImageFormatEnum {
-    #[strum(serialize = "png")]
-    PNG,
-    #[strum(serialize = "dds")]
-    DDS,
-    #[strum(serialize = "tga")]
-    TGA,
-    #[strum(serialize = "JPEG")]
-    JPEG,
-    #[strum(serialize = "JPG")]
-    JPG,
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+/// Key of [`Settings`] in the eframe storage.
+const SETTINGS_KEY: &str = "settings";
+const MAX_RECENT_FOLDERS: usize = 8;
+/// Oldest activity log lines are dropped past this.
+const MAX_ACTIVITY_LOG_LINES: usize = 200;
+
+/// What is restored on the next start. The window size and position are saved by eframe itself.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    source_dir: Option<String>,
+    dest_dir: Option<String>,
+    options: ConvertOptions,
+    scan: ScanOptions,
+    selected_preset: Option<String>,
+    /// Most recent first.
+    recent_folders: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            source_dir: None,
+            dest_dir: None,
+            options: ConvertOptions {
+                cache: CacheMode::Incremental,
+                ..Default::default()
+            },
+            scan: ScanOptions::default(),
+            selected_preset: None,
+            recent_folders: Vec::new(),
+        }
+    }
 }
 
 pub(crate) struct ImageConverterApp {
     selected_source_dir: Option<String>,
     selected_dest_dir: Option<String>,
     files: Option<Vec<String>>,
+    scan: ScanOptions,
+    /// One glob per line, parsed into `scan` when edited.
+    include_patterns: String,
+    exclude_patterns: String,
+    /// Why the last scan failed, e.g. an invalid glob.
+    scan_error: Option<String>,
+    /// Set while a scan is running, `files` grows as it sends images.
+    scan_rx: Option<Receiver<ScanEvent>>,
+    scan_cancel: CancellationToken,
     output_format: ImageFormatEnum,
     dds_format: image_dds::ImageFormat,
-    selected_row_index: i8,
+    dds_quality: image_dds::Quality,
+    mipmaps: MipmapPolicy,
+    webp: WebpCompression,
+    dds_rules: Vec<DdsRule>,
+    presets: Presets,
+    selected_preset: Option<String>,
+    preset_name: String,
+    recent_folders: Vec<String>,
+    selected_row_index: Option<usize>,
     is_window_open: bool,
-    is_convert_success: Option<i8>,
+    convert_report: Option<ConversionReport>,
+    convert_progress: Progress,
+    current_file: Option<PathBuf>,
+    cancel_token: CancellationToken,
     #[cfg(debug_assertions)]
     is_debug_panel_open: bool,
     set_window_open_flag: bool,
     use_sequential_convert: bool,
-    tx: Sender<i8>,
-    rx: Receiver<i8>,
+    error_policy: ErrorPolicy,
+    overwrite: OverwritePolicy,
+    collision: CollisionPolicy,
+    /// Not editable in the app yet, kept so the saved settings round trip.
+    format_preference: Vec<ImageFormatEnum>,
+    skip_unchanged: bool,
+    /// Only for the next conversion, cleared once it starts.
+    force_rebuild: bool,
+    tx: Sender<ConvertEvent>,
+    rx: Receiver<ConvertEvent>,
+    /// Set while the source folder is watched, dropping it stops watching.
+    watcher: Option<FolderWatcher>,
+    watch_tx: Sender<WatchEvent>,
+    watch_rx: Receiver<WatchEvent>,
+    activity_log: Vec<String>,
+    /// Dry run shown for review before converting.
+    plan: Option<ConversionPlan>,
 }
 
 impl eframe::App for ImageConverterApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let settings = Settings {
+            source_dir: self.selected_source_dir.clone(),
+            dest_dir: self.selected_dest_dir.clone(),
+            options: self.convert_options(),
+            scan: self.scan.clone(),
+            selected_preset: self.selected_preset.clone(),
+            recent_folders: self.recent_folders.clone(),
+        };
+        eframe::set_value(storage, SETTINGS_KEY, &settings);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui_extras::install_image_loaders(ctx);
 
         // main panel
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.set_enabled(!self.is_window_open);
+            ui.set_enabled(!self.is_window_open && self.plan.is_none());
 
             ui.horizontal(|ui| {
                 if ui.button("Select folder").clicked() {
                     let files = rfd::FileDialog::new().set_directory("/").pick_folder();
 
-                    if files.is_some() {
-                        self.selected_source_dir =
-                            Some(files.unwrap().to_str().unwrap().to_string());
-                        self.files = None;
-                        info!("source dir: {:?}", self.selected_source_dir);
+                    if let Some(folder) = files {
+                        self.set_source_dir(folder.to_str().unwrap().to_string());
                     }
                 }
 
+                ui.add_enabled_ui(!self.recent_folders.is_empty(), |ui| {
+                    ui.menu_button("Recent", |ui| {
+                        let mut selected = None;
+                        for folder in &self.recent_folders {
+                            if ui.button(folder).clicked() {
+                                selected = Some(folder.clone());
+                                ui.close_menu();
+                            }
+                        }
+                        if let Some(folder) = selected {
+                            self.set_source_dir(folder);
+                        }
+                    });
+                });
+
                 ui.label(format!("Source folder: {:?}", self.selected_source_dir));
             });
             ui.horizontal(|ui| {
                 if ui.button("Select folder").clicked() {
                     let files = rfd::FileDialog::new().set_directory("/").pick_folder();
 
-                    if files.is_some() {
-                        self.selected_dest_dir = Some(files.unwrap().to_str().unwrap().to_string());
+                    if let Some(folder) = files {
+                        self.selected_dest_dir = Some(folder.to_str().unwrap().to_string());
                         info!("dest dir: {:?}", self.selected_source_dir);
                     }
                 }
@@ -78,36 +164,27 @@ impl eframe::App for ImageConverterApp {
             });
 
             if self.selected_source_dir.is_some() && self.files.is_none() {
-                let selected_source_dir = self.selected_source_dir.clone();
-
-                self.files = Some(
-                    WalkDir::new(selected_source_dir.unwrap())
-                        .into_iter()
-                        .filter_map(|file| file.ok())
-                        .filter(|file| file.metadata().unwrap().is_file())
-                        .filter(|file| {
-                            let array: [String; 5] = [
-                                "dds".parse().unwrap(),
-                                "png".parse().unwrap(),
-                                "jpg".parse().unwrap(),
-                                "jpeg".parse().unwrap(),
-                                "tga".parse().unwrap(),
-                            ];
-                            array.contains(
-                                &file
-                                    .path()
-                                    .extension()
-                                    .unwrap_or("None".as_ref())
-                                    .to_str()
-                                    .unwrap()
-                                    .to_string(),
-                            )
-                        })
-                        .map(|file| String::from(file.path().to_str().unwrap()))
-                        .collect(),
-                );
-                debug!("files: {:?}", self.files);
+                self.start_scan();
             }
+            self.receive_scan_events();
+
+            egui::CollapsingHeader::new("Scan filters").show(ui, |ui| self.scan_filters_ui(ui));
+            if let Some(scan_error) = &self.scan_error {
+                ui.colored_label(ui.visuals().error_fg_color, scan_error);
+            }
+            if self.scan_rx.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!(
+                        "scanning… {} files",
+                        self.files.as_ref().map_or(0, Vec::len)
+                    ));
+                });
+                // files arrive from the scan thread without user input
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+
+            ui.horizontal(|ui| self.presets_ui(ui));
 
             ui.horizontal(|ui| {
                 egui::ComboBox::from_label("Output Format")
@@ -118,12 +195,25 @@ impl eframe::App for ImageConverterApp {
 
                         ui.selectable_value(&mut self.output_format, ImageFormatEnum::PNG, "PNG");
                         ui.selectable_value(&mut self.output_format, ImageFormatEnum::DDS, "DDS");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::KTX2, "KTX2");
                         ui.selectable_value(&mut self.output_format, ImageFormatEnum::JPEG, "JPEG");
                         ui.selectable_value(&mut self.output_format, ImageFormatEnum::JPG, "JPG");
                         ui.selectable_value(&mut self.output_format, ImageFormatEnum::TGA, "TGA");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::WEBP, "WEBP");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::BMP, "BMP");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::TIFF, "TIFF");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::TIF, "TIF");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::GIF, "GIF");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::ICO, "ICO");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::ICNS, "ICNS");
+                        ui.selectable_value(&mut self.output_format, ImageFormatEnum::EXR, "EXR");
                     });
 
-                if self.output_format == ImageFormatEnum::DDS {
+                if self.output_format == ImageFormatEnum::WEBP {
+                    webp_ui(ui, &mut self.webp);
+                }
+
+                if self.output_format.is_gpu_texture() {
                     egui::ComboBox::from_label("DDS Format")
                         .selected_text(format!("{:?}", self.dds_format))
                         .show_ui(ui, |ui| {
@@ -138,42 +228,125 @@ impl eframe::App for ImageConverterApp {
                                 );
                             }
                         });
+
+                    egui::ComboBox::from_label("Quality")
+                        .selected_text(self.dds_quality.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.style_mut().wrap = Some(false);
+                            ui.set_min_width(60.0);
+
+                            for quality in image_dds::Quality::iter() {
+                                ui.selectable_value(
+                                    &mut self.dds_quality,
+                                    quality,
+                                    quality.to_string(),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text("Slower compression looks better");
+
+                    mipmaps_ui(ui, "mipmaps", &mut self.mipmaps);
+                    ui.label("Mipmaps");
                 }
-            });
 
-            ui.add_enabled_ui(
-                self.files.as_ref().map_or(false, |vec| !vec.is_empty()),
-                |ui| {
-                    if ui.button("Convert").clicked() {
-                        self.is_convert_success = None;
-                        self.is_window_open = true;
-
-                        let files = self.files.clone().unwrap();
-                        let selected_source_dir = self.selected_source_dir.clone().unwrap();
-                        let selected_dist_dir = self
-                            .selected_dest_dir
-                            .clone()
-                            .unwrap_or(self.selected_source_dir.clone().unwrap());
-                        let output_format = self.output_format.clone();
-                        let dds_format = self.dds_format.clone();
-                        let use_sequential_convert = self.use_sequential_convert.clone();
-
-                        let tx = self.tx.clone();
-
-                        thread::spawn(move || {
-                            let result = convert(
-                                files,
-                                selected_source_dir,
-                                selected_dist_dir,
-                                output_format,
-                                dds_format,
-                                use_sequential_convert,
+                ui.checkbox(&mut self.skip_unchanged, "Skip unchanged")
+                    .on_hover_text(
+                        "Only convert files whose content or settings changed since the last conversion",
+                    );
+                ui.add_enabled(
+                    self.skip_unchanged,
+                    egui::Checkbox::new(&mut self.force_rebuild, "Force rebuild"),
+                )
+                .on_hover_text("Convert every file once more, even unchanged ones");
+
+                let mut continue_on_error = self.error_policy == ErrorPolicy::Continue;
+                if ui
+                    .checkbox(&mut continue_on_error, "Continue on error")
+                    .on_hover_text("Keep converting the other files when one fails")
+                    .changed()
+                {
+                    self.error_policy = if continue_on_error {
+                        ErrorPolicy::Continue
+                    } else {
+                        ErrorPolicy::FailFast
+                    };
+                }
+
+                egui::ComboBox::from_label("If output exists")
+                    .selected_text(overwrite_label(self.overwrite))
+                    .show_ui(ui, |ui| {
+                        ui.style_mut().wrap = Some(false);
+                        for policy in OverwritePolicy::iter() {
+                            ui.selectable_value(
+                                &mut self.overwrite,
+                                policy,
+                                overwrite_label(policy),
                             );
-                            tx.send(result).expect("failed to send result");
-                        });
-                    }
-                },
-            );
+                        }
+                    });
+
+                egui::ComboBox::from_label("If outputs collide")
+                    .selected_text(collision_label(self.collision))
+                    .show_ui(ui, |ui| {
+                        ui.style_mut().wrap = Some(false);
+                        for policy in CollisionPolicy::iter() {
+                            ui.selectable_value(
+                                &mut self.collision,
+                                policy,
+                                collision_label(policy),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "When several images have the same output, e.g. a.png and a.tga to a.dds",
+                    );
+            });
+
+            if self.output_format.is_gpu_texture() {
+                egui::CollapsingHeader::new(format!("DDS rules ({})", self.dds_rules.len()))
+                    .show(ui, |ui| self.dds_rules_ui(ui));
+            }
+
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(
+                    self.scan_rx.is_none() && self.files.as_ref().is_some_and(|vec| !vec.is_empty()),
+                    |ui| {
+                        if ui.button("Convert").clicked() {
+                            let files = self.files.clone().unwrap();
+                            self.start_conversion(files);
+                        }
+                        if ui
+                            .button("Preview")
+                            .on_hover_text("Show what converting would do without writing anything")
+                            .clicked()
+                        {
+                            self.plan = Some(self.plan_conversion());
+                        }
+                    },
+                );
+
+                let mut is_watching = self.watcher.is_some();
+                if ui
+                    .add_enabled(
+                        self.selected_source_dir.is_some(),
+                        egui::Checkbox::new(&mut is_watching, "Watch folder"),
+                    )
+                    .on_hover_text(
+                        "Convert images as they are saved to the source folder, with the settings at the time watching started",
+                    )
+                    .changed()
+                {
+                    self.set_watching(is_watching);
+                }
+            });
+
+            if self.watcher.is_some() || !self.activity_log.is_empty() {
+                egui::CollapsingHeader::new("Activity")
+                    .default_open(true)
+                    .show(ui, |ui| self.activity_log_ui(ui));
+            }
 
             #[cfg(debug_assertions)]
             if ui.button("debug").clicked() {
@@ -189,16 +362,17 @@ impl eframe::App for ImageConverterApp {
                 });
 
                 // show image when column selected
-                if self.selected_row_index > -1 {
-                    let files = self.files.clone().unwrap();
-                    let index = self.selected_row_index.clone();
-                    let current_file = &files[index as usize];
+                // the list may have shrunk by a rescan since the row was selected
+                let selected_file = self
+                    .selected_row_index
+                    .and_then(|index| self.files.as_ref()?.get(index).cloned());
+                if let Some(current_file) = selected_file {
 
                     // in order to get top pos of table, subtract table header height from y pos
                     let dimension_label_pos =
                         Pos2::new(first_row_top_right_pos.x, first_row_top_right_pos.y - 20.0);
                     let image_dimension =
-                        self.get_image_dimension(current_file).unwrap_or_else(|e| {
+                        self.get_image_dimension(&current_file).unwrap_or_else(|e| {
                             error!("failed to get image dimension {:?}", e);
                             (0, 0)
                         });
@@ -220,19 +394,36 @@ impl eframe::App for ImageConverterApp {
                 });
             });
 
-            // get signal when convert is done
-            let result = self.rx.try_recv();
-            match result {
-                Ok(success) => {
-                    self.is_convert_success = Some(success);
-                }
-                Err(e) => {
-                    if e == TryRecvError::Disconnected {
-                        error!("{:?}", e);
+            // get progress of the running convert, the last event carries the report
+            loop {
+                match self.rx.try_recv() {
+                    Ok(ConvertEvent::Started { total }) => {
+                        self.convert_progress.total = total;
+                    }
+                    Ok(ConvertEvent::FileStarted { input }) => {
+                        self.current_file = Some(input);
+                    }
+                    Ok(ConvertEvent::FileFinished { progress, .. }) => {
+                        self.convert_progress = progress;
+                    }
+                    Ok(ConvertEvent::Finished(report)) => {
+                        self.convert_report = Some(report);
+                    }
+                    Err(e) => {
+                        if e == TryRecvError::Disconnected {
+                            error!("{:?}", e);
+                        }
+                        break;
                     }
                 }
             }
 
+            self.receive_watch_events();
+            if self.watcher.is_some() {
+                // events come from another thread, repaint to show them
+                ctx.request_repaint_after(Duration::from_millis(250));
+            }
+
             if self.set_window_open_flag {
                 self.is_window_open = false;
                 self.set_window_open_flag = false;
@@ -252,22 +443,60 @@ impl eframe::App for ImageConverterApp {
             .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
             .show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
-                    if let Some(is_convert_success) = self.is_convert_success {
-                        if is_convert_success >= 0 {
+                    if let Some(report) = &self.convert_report {
+                        if report.cancelled {
+                            ui.label(RichText::new("Cancelled").color(Color32::YELLOW).size(30.0));
+                        } else if report.is_success() {
                             ui.label(RichText::new("Success!").color(Color32::GREEN).size(30.0));
                         } else {
                             ui.label(RichText::new("Failed!").color(Color32::RED).size(30.0));
                         }
+                        Self::report_ui(ui, report);
                         if ui.button("Done!").clicked() {
                             self.set_window_open_flag = true;
                         }
                     } else {
-                        ui.add(egui::Spinner::new().size(100.0));
-                        ui.label(RichText::new("converting...").size(30.0));
+                        Self::progress_ui(ui, &self.convert_progress, &self.current_file);
+                        if self.cancel_token.is_cancelled() {
+                            ui.label("cancelling, waiting for files in progress...");
+                        } else if ui.button("Cancel").clicked() {
+                            info!("convert cancelled");
+                            self.cancel_token.cancel();
+                        }
+                        // events arrive without user input, keep polling the channel
+                        ctx.request_repaint();
                     }
                 });
             });
 
+        // plan panel
+        let mut is_plan_open = self.plan.is_some();
+        let mut execute_plan = None;
+        if let Some(plan) = &self.plan {
+            egui::Window::new("Plan")
+                .open(&mut is_plan_open)
+                .collapsible(false)
+                .default_pos(center_pos)
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .show(ctx, |ui| {
+                    Self::plan_ui(ui, plan);
+                    let files = plan.files_to_convert();
+                    if ui
+                        .add_enabled(!files.is_empty(), egui::Button::new("Execute"))
+                        .clicked()
+                    {
+                        execute_plan = Some(files);
+                    }
+                });
+        }
+        if !is_plan_open {
+            self.plan = None;
+        }
+        if let Some(files) = execute_plan {
+            self.plan = None;
+            self.start_conversion(files);
+        }
+
         // debug panel
         #[cfg(debug_assertions)]
         egui::Window::new("Debug Panel")
@@ -306,8 +535,12 @@ impl eframe::App for ImageConverterApp {
                         ui.label(format!("{:?}", self.is_window_open));
                         ui.end_row();
 
-                        ui.label("is convert success");
-                        ui.label(format!("{:?}", self.is_convert_success));
+                        ui.label("convert report");
+                        ui.label(
+                            self.convert_report
+                                .as_ref()
+                                .map_or("None".to_string(), |report| report.to_string()),
+                        );
                         ui.end_row();
 
                         ui.label("use sequential convert");
@@ -319,23 +552,550 @@ impl eframe::App for ImageConverterApp {
 }
 
 impl ImageConverterApp {
-    pub fn new(_cc: &eframe::CreationContext) -> Self {
+    pub fn new(cc: &eframe::CreationContext) -> Self {
         let (tx, rx) = channel();
+        let (watch_tx, watch_rx) = channel();
+        let settings: Settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, SETTINGS_KEY))
+            .unwrap_or_default();
+        // folders may have been moved or deleted since the last session
+        let existing_dir = |dir: Option<String>| dir.filter(|dir| Path::new(dir).is_dir());
+        let options = settings.options;
+
+        let presets = Presets::default_path()
+            .map(|path| {
+                Presets::load(&path).unwrap_or_else(|e| {
+                    error!("failed to load presets: {:?}", e);
+                    Presets::default()
+                })
+            })
+            .unwrap_or_default();
+
+        let selected_preset = settings
+            .selected_preset
+            .filter(|name| presets.get(name).is_some());
+
         Self {
-            selected_source_dir: None,
-            selected_dest_dir: None,
+            selected_source_dir: existing_dir(settings.source_dir),
+            selected_dest_dir: existing_dir(settings.dest_dir),
             files: None,
-            output_format: ImageFormatEnum::PNG,
-            dds_format: image_dds::ImageFormat::BC1RgbaUnorm,
-            selected_row_index: -1,
+            include_patterns: settings.scan.include.join("\n"),
+            exclude_patterns: settings.scan.exclude.join("\n"),
+            scan: settings.scan,
+            scan_error: None,
+            scan_rx: None,
+            scan_cancel: CancellationToken::new(),
+            output_format: options.output_format,
+            dds_format: options.dds_format,
+            dds_quality: options.dds_quality,
+            mipmaps: options.mipmaps,
+            webp: options.webp,
+            dds_rules: options.dds_rules,
+            presets,
+            preset_name: selected_preset.clone().unwrap_or_default(),
+            selected_preset,
+            recent_folders: settings.recent_folders,
+            selected_row_index: None,
             is_window_open: false,
-            is_convert_success: None,
+            convert_report: None,
+            convert_progress: Progress::default(),
+            current_file: None,
+            cancel_token: CancellationToken::new(),
             #[cfg(debug_assertions)]
             is_debug_panel_open: false,
             set_window_open_flag: false,
-            use_sequential_convert: false,
+            use_sequential_convert: options.use_sequential_convert,
+            error_policy: options.error_policy,
+            overwrite: options.overwrite,
+            collision: options.collision,
+            format_preference: options.format_preference,
+            skip_unchanged: options.cache != CacheMode::Off,
+            force_rebuild: false,
             tx,
             rx,
+            watcher: None,
+            watch_tx,
+            watch_rx,
+            activity_log: Vec::new(),
+            plan: None,
+        }
+    }
+
+    /// Lists the images of the source folder on another thread, stopping the previous scan.
+    fn start_scan(&mut self) {
+        let Some(source_dir) = self.selected_source_dir.clone() else {
+            return;
+        };
+        self.scan_cancel.cancel();
+        self.scan_rx = None;
+        self.selected_row_index = None;
+        // an empty list until the first images arrive, and no rescan every frame on errors
+        self.files = Some(Vec::new());
+
+        let scanner = match ImageScanner::new(&source_dir, &self.scan) {
+            Ok(scanner) => scanner,
+            Err(e) => {
+                error!("failed to scan: {:?}", e);
+                self.scan_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.scan_error = None;
+
+        let (tx, rx) = channel();
+        let cancel = CancellationToken::new();
+        self.scan_rx = Some(rx);
+        self.scan_cancel = cancel.clone();
+        thread::spawn(move || scanner.scan_with_progress(tx, &cancel));
+    }
+
+    fn receive_scan_events(&mut self) {
+        let Some(scan_rx) = &self.scan_rx else {
+            return;
+        };
+        loop {
+            match scan_rx.try_recv() {
+                Ok(ScanEvent::Found(found)) => {
+                    self.files.get_or_insert_with(Vec::new).extend(found);
+                }
+                Ok(ScanEvent::Finished) => {
+                    debug!("files: {:?}", self.files);
+                    self.scan_rx = None;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    error!("scan stopped without finishing");
+                    self.scan_rx = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn scan_filters_ui(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+
+        egui::Grid::new("scan_filters")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Include").on_hover_text(
+                    "One glob per line relative to the source folder, e.g. textures/**. Every image when empty",
+                );
+                if ui
+                    .add(egui::TextEdit::multiline(&mut self.include_patterns).desired_rows(2))
+                    .changed()
+                {
+                    self.scan.include = glob_lines(&self.include_patterns);
+                    changed = true;
+                }
+                ui.end_row();
+
+                ui.label("Exclude")
+                    .on_hover_text("One glob per line relative to the source folder, e.g. **/old/**");
+                if ui
+                    .add(egui::TextEdit::multiline(&mut self.exclude_patterns).desired_rows(2))
+                    .changed()
+                {
+                    self.scan.exclude = glob_lines(&self.exclude_patterns);
+                    changed = true;
+                }
+                ui.end_row();
+
+                ui.label("Max depth");
+                ui.horizontal(|ui| {
+                    let mut limited = self.scan.max_depth.is_some();
+                    if ui.checkbox(&mut limited, "").changed() {
+                        self.scan.max_depth = limited.then_some(1);
+                        changed = true;
+                    }
+                    if let Some(max_depth) = &mut self.scan.max_depth {
+                        changed |= ui
+                            .add(egui::DragValue::new(max_depth).clamp_range(1..=64))
+                            .on_hover_text("1 only takes the images directly in the source folder")
+                            .changed();
+                    }
+                });
+                ui.end_row();
+            });
+
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut self.scan.include_hidden, "Hidden files")
+                .on_hover_text("Also take files and folders whose name starts with a dot")
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.scan.follow_symlinks, "Follow symlinks")
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.scan.use_ignore_file, "Use ignore files")
+                .on_hover_text(format!(
+                    "Leave out the files listed in the {} files of the source folder",
+                    IGNORE_FILE_NAME
+                ))
+                .changed();
+        });
+
+        if changed {
+            self.files = None;
+            self.selected_row_index = None;
+        }
+    }
+
+    /// Selects a source folder and moves it to the top of the recent folders.
+    fn set_source_dir(&mut self, folder: String) {
+        info!("source dir: {:?}", folder);
+
+        self.recent_folders.retain(|recent| *recent != folder);
+        self.recent_folders.insert(0, folder.clone());
+        self.recent_folders.truncate(MAX_RECENT_FOLDERS);
+
+        self.selected_source_dir = Some(folder);
+        self.files = None;
+        self.selected_row_index = None;
+        if self.watcher.is_some() {
+            // watch the new folder instead
+            self.set_watching(true);
+        }
+    }
+
+    fn set_watching(&mut self, watch: bool) {
+        self.watcher = None;
+        let Some(source_dir) = self.selected_source_dir.clone().filter(|_| watch) else {
+            self.log_activity("stopped watching".to_string());
+            return;
+        };
+
+        let dest_dir = self.selected_dest_dir.clone().unwrap_or(source_dir.clone());
+        match FolderWatcher::new(
+            source_dir.clone(),
+            dest_dir,
+            self.convert_options(),
+            &self.scan,
+            DEFAULT_DEBOUNCE,
+            self.watch_tx.clone(),
+        ) {
+            Ok(watcher) => {
+                self.watcher = Some(watcher);
+                self.log_activity(format!("watching {}", source_dir));
+            }
+            Err(e) => {
+                error!("failed to watch {:?}: {:?}", source_dir, e);
+                self.log_activity(format!("failed to watch {}: {}", source_dir, e));
+            }
+        }
+    }
+
+    fn receive_watch_events(&mut self) {
+        while let Ok(event) = self.watch_rx.try_recv() {
+            match event {
+                WatchEvent::Changed(files) => {
+                    for file in &files {
+                        self.log_activity(format!("changed: {}", file.display()));
+                    }
+                    // pick up new images in the file list
+                    self.files = None;
+                }
+                WatchEvent::Converted(report) => {
+                    for failed in report.failed() {
+                        self.log_activity(format!(
+                            "failed: {} ({})",
+                            failed.input.display(),
+                            failed.error.as_deref().unwrap_or_default()
+                        ));
+                    }
+                    self.log_activity(report.to_string());
+                }
+                WatchEvent::Error(e) => self.log_activity(format!("error: {}", e)),
+            }
+        }
+    }
+
+    fn log_activity(&mut self, line: String) {
+        self.activity_log.push(line);
+        if self.activity_log.len() > MAX_ACTIVITY_LOG_LINES {
+            let overflow = self.activity_log.len() - MAX_ACTIVITY_LOG_LINES;
+            self.activity_log.drain(..overflow);
+        }
+    }
+
+    fn activity_log_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical()
+            .max_height(100.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in &self.activity_log {
+                    ui.label(line);
+                }
+            });
+        if ui.button("Clear").clicked() {
+            self.activity_log.clear();
+        }
+    }
+
+    fn output_dir(&self) -> Option<String> {
+        self.selected_dest_dir
+            .clone()
+            .or(self.selected_source_dir.clone())
+    }
+
+    fn start_conversion(&mut self, files: Vec<String>) {
+        let (Some(source_dir), Some(output_dir)) =
+            (self.selected_source_dir.clone(), self.output_dir())
+        else {
+            return;
+        };
+
+        self.convert_report = None;
+        self.convert_progress = Progress::default();
+        self.current_file = None;
+        self.cancel_token = CancellationToken::new();
+        self.is_window_open = true;
+
+        let options = self.convert_options();
+        self.force_rebuild = false;
+
+        let tx = self.tx.clone();
+        let cancel_token = self.cancel_token.clone();
+
+        thread::spawn(move || {
+            convert_with_progress(files, source_dir, output_dir, &options, tx, cancel_token);
+        });
+    }
+
+    fn plan_conversion(&self) -> ConversionPlan {
+        match (&self.files, &self.selected_source_dir, self.output_dir()) {
+            (Some(files), Some(source_dir), Some(output_dir)) => {
+                plan(files, source_dir, &output_dir, &self.convert_options())
+            }
+            _ => ConversionPlan::default(),
+        }
+    }
+
+    fn convert_options(&self) -> ConvertOptions {
+        ConvertOptions {
+            output_format: self.output_format,
+            dds_format: self.dds_format,
+            dds_quality: self.dds_quality,
+            mipmaps: self.mipmaps,
+            webp: self.webp,
+            dds_rules: self.dds_rules.clone(),
+            use_sequential_convert: self.use_sequential_convert,
+            overwrite: self.overwrite,
+            collision: self.collision,
+            format_preference: self.format_preference.clone(),
+            cache: match (self.skip_unchanged, self.force_rebuild) {
+                (false, _) => CacheMode::Off,
+                (true, false) => CacheMode::Incremental,
+                (true, true) => CacheMode::Rebuild,
+            },
+            error_policy: self.error_policy,
+        }
+    }
+
+    fn apply_preset(&mut self, preset: &Preset) {
+        let mut options = self.convert_options();
+        preset.apply(&mut options);
+
+        self.output_format = options.output_format;
+        self.dds_format = options.dds_format;
+        self.dds_quality = options.dds_quality;
+        self.mipmaps = options.mipmaps;
+        self.dds_rules = options.dds_rules;
+        self.webp = options.webp;
+        self.selected_preset = Some(preset.name.clone());
+        self.preset_name.clone_from(&preset.name);
+    }
+
+    fn save_presets(&self) {
+        let Some(path) = Presets::default_path() else {
+            error!("no config folder to save presets to");
+            return;
+        };
+        if let Err(e) = self.presets.save(&path) {
+            error!("failed to save presets: {:?}", e);
+        }
+    }
+
+    /// Preset dropdown with save, rename, delete, export and import.
+    fn presets_ui(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        egui::ComboBox::from_label("Preset")
+            .selected_text(self.selected_preset.as_deref().unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                ui.style_mut().wrap = Some(false);
+                ui.set_min_width(120.0);
+
+                for preset in self.presets.iter() {
+                    let is_selected = self.selected_preset.as_ref() == Some(&preset.name);
+                    if ui.selectable_label(is_selected, &preset.name).clicked() {
+                        selected = Some(preset.clone());
+                    }
+                }
+            });
+        if let Some(preset) = selected {
+            info!("preset selected: {:?}", preset.name);
+            self.apply_preset(&preset);
+        }
+
+        ui.add(
+            egui::TextEdit::singleline(&mut self.preset_name)
+                .hint_text("preset name")
+                .desired_width(120.0),
+        );
+        let name = self.preset_name.trim().to_string();
+
+        if ui
+            .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+            .on_hover_text("Save the current settings, replacing a preset with the same name")
+            .clicked()
+        {
+            self.presets
+                .insert(Preset::from_options(name.clone(), &self.convert_options()));
+            self.selected_preset = Some(name.clone());
+            self.save_presets();
+        }
+
+        let selected_name = self.selected_preset.clone();
+        let can_rename = selected_name
+            .as_ref()
+            .is_some_and(|selected| !name.is_empty() && *selected != name);
+        if ui
+            .add_enabled(can_rename, egui::Button::new("Rename"))
+            .clicked()
+        {
+            if let Some(selected) = &selected_name {
+                match self.presets.rename(selected, &name) {
+                    Ok(()) => {
+                        self.selected_preset = Some(name.clone());
+                        self.save_presets();
+                    }
+                    Err(e) => error!("failed to rename preset: {:?}", e),
+                }
+            }
+        }
+
+        if ui
+            .add_enabled(selected_name.is_some(), egui::Button::new("Delete"))
+            .clicked()
+        {
+            if let Some(selected) = &selected_name {
+                self.presets.remove(selected);
+                self.selected_preset = None;
+                self.save_presets();
+            }
+        }
+
+        if ui
+            .add_enabled(selected_name.is_some(), egui::Button::new("Export"))
+            .clicked()
+        {
+            let preset = selected_name
+                .as_ref()
+                .and_then(|selected| self.presets.get(selected));
+            if let Some(preset) = preset {
+                let path = rfd::FileDialog::new()
+                    .add_filter("Preset", &["toml", "json"])
+                    .set_file_name(format!("{}.toml", preset.name))
+                    .save_file();
+                if let Some(path) = path {
+                    if let Err(e) = preset.export(&path) {
+                        error!("failed to export preset: {:?}", e);
+                    }
+                }
+            }
+        }
+
+        if ui.button("Import").clicked() {
+            let path = rfd::FileDialog::new()
+                .add_filter("Preset", &["toml", "json"])
+                .pick_file();
+            if let Some(path) = path {
+                match Preset::import(&path) {
+                    Ok(preset) => {
+                        info!("imported preset {:?} from {:?}", preset.name, path);
+                        self.apply_preset(&preset);
+                        self.presets.insert(preset);
+                        self.save_presets();
+                    }
+                    Err(e) => error!("failed to import preset: {:?}", e),
+                }
+            }
+        }
+    }
+
+    /// Per-file DDS settings, the first rule whose pattern matches a file is used.
+    fn dds_rules_ui(&mut self, ui: &mut egui::Ui) {
+        let mut removed_index = None;
+
+        egui::Grid::new("dds_rules_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Pattern")
+                    .on_hover_text("Glob like *_nrm.* or a file name suffix like _nrm");
+                ui.label("DDS Format");
+                ui.label("Quality");
+                ui.label("Mipmaps");
+                ui.label("sRGB");
+                ui.end_row();
+
+                for (index, rule) in self.dds_rules.iter_mut().enumerate() {
+                    let is_valid = rule.matches(Path::new("")).is_ok();
+                    let pattern = ui.add(
+                        egui::TextEdit::singleline(&mut rule.pattern)
+                            .desired_width(120.0)
+                            .text_color_opt((!is_valid).then_some(Color32::RED)),
+                    );
+                    if !is_valid {
+                        pattern.on_hover_text("invalid pattern");
+                    }
+
+                    egui::ComboBox::from_id_source(("dds_rule_format", index))
+                        .selected_text(rule.dds_format.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.style_mut().wrap = Some(false);
+                            for format in image_dds::ImageFormat::iter() {
+                                ui.selectable_value(
+                                    &mut rule.dds_format,
+                                    format,
+                                    format.to_string(),
+                                );
+                            }
+                        });
+
+                    egui::ComboBox::from_id_source(("dds_rule_quality", index))
+                        .selected_text(rule.quality.to_string())
+                        .show_ui(ui, |ui| {
+                            for quality in image_dds::Quality::iter() {
+                                ui.selectable_value(
+                                    &mut rule.quality,
+                                    quality,
+                                    quality.to_string(),
+                                );
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        mipmaps_ui(ui, ("dds_rule_mipmaps", index), &mut rule.mipmaps);
+                    });
+
+                    ui.checkbox(&mut rule.srgb, "");
+
+                    if ui.button(egui_phosphor::regular::TRASH).clicked() {
+                        removed_index = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = removed_index {
+            self.dds_rules.remove(index);
+        }
+
+        if ui.button("Add rule").clicked() {
+            self.dds_rules
+                .push(DdsRule::new("_nrm", image_dds::ImageFormat::BC5RgUnorm));
         }
     }
 
@@ -365,7 +1125,7 @@ impl ImageConverterApp {
                         body.row(30.0, |mut row| {
                             let row_index = row.index();
 
-                            row.set_selected(self.selected_row_index == row_index as i8);
+                            row.set_selected(self.selected_row_index == Some(row_index));
                             row.col(|ui| {
                                 ui.label(file_path);
                             });
@@ -389,14 +1149,14 @@ impl ImageConverterApp {
                 .put(
                     Rect::from_min_size(refresh_button_pos, Vec2::new(20.0, 20.0)),
                     egui::Button::new(
-                        RichText::new(format!("{}", egui_phosphor::regular::ARROW_CLOCKWISE))
+                        RichText::new(egui_phosphor::regular::ARROW_CLOCKWISE.to_string())
                             .size(20.0),
                     ),
                 )
                 .clicked()
             {
                 // set to initial value to prevent render image
-                self.selected_row_index = -1;
+                self.selected_row_index = None;
                 self.files = None;
             }
         }
@@ -404,12 +1164,160 @@ impl ImageConverterApp {
         row_top_right_corner_pos
     }
 
+    fn progress_ui(ui: &mut egui::Ui, progress: &Progress, current_file: &Option<PathBuf>) {
+        ui.label(RichText::new("converting...").size(30.0));
+        ui.add(
+            egui::ProgressBar::new(progress.fraction())
+                .desired_width(400.0)
+                .show_percentage(),
+        );
+        ui.label(format!("{} / {} files", progress.completed, progress.total));
+        if let Some(current_file) = current_file {
+            ui.label(current_file.display().to_string());
+        }
+        ui.label(format!(
+            "{}/s, {} left",
+            format_bytes(progress.bytes_per_second() as u64),
+            progress
+                .eta
+                .map_or("--".to_string(), |eta| format!("{}s", eta.as_secs()))
+        ));
+    }
+
+    fn plan_ui(ui: &mut egui::Ui, plan: &ConversionPlan) {
+        use egui_extras::{Column, TableBuilder};
+
+        ui.label(format!(
+            "{} of {} files to convert, {} collisions",
+            plan.files_to_convert().len(),
+            plan.files.len(),
+            plan.collision_count()
+        ));
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto())
+            .column(Column::initial(250.0).resizable(true).clip(true))
+            .column(Column::initial(250.0).resizable(true).clip(true))
+            .column(Column::remainder().at_least(100.0))
+            .max_scroll_height(300.0)
+            .header(20.0, |mut header| {
+                for title in ["Action", "File", "Output", "Format"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, plan.files.len(), |mut row| {
+                    let file = &plan.files[row.index()];
+                    let color = match file.action {
+                        PlannedAction::Write(OutputAction::Created) => Color32::GREEN,
+                        PlannedAction::Write(_) => Color32::YELLOW,
+                        PlannedAction::Skip(_) => Color32::GRAY,
+                        PlannedAction::Collision | PlannedAction::Error(_) => Color32::RED,
+                    };
+                    row.col(|ui| {
+                        ui.label(RichText::new(file.action.to_string()).color(color));
+                    });
+                    row.col(|ui| {
+                        ui.label(file.input.display().to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(
+                            file.output
+                                .as_ref()
+                                .map_or(String::new(), |output| output.display().to_string()),
+                        );
+                    });
+                    row.col(|ui| {
+                        ui.label(&file.format);
+                    });
+                });
+            });
+    }
+
+    fn report_ui(ui: &mut egui::Ui, report: &ConversionReport) {
+        use egui_extras::{Column, TableBuilder};
+
+        ui.label(report.to_string());
+        ui.label(format!(
+            "{} -> {}",
+            format_bytes(report.input_bytes()),
+            format_bytes(report.output_bytes())
+        ));
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto())
+            .column(Column::initial(250.0).resizable(true).clip(true))
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::remainder().at_least(150.0))
+            .max_scroll_height(300.0)
+            .header(20.0, |mut header| {
+                for title in ["Status", "File", "Time", "Size", "Message"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, report.files.len(), |mut row| {
+                    let file = &report.files[row.index()];
+                    let color = match file.status {
+                        FileStatus::Converted => Color32::GREEN,
+                        FileStatus::Skipped(_) => Color32::GRAY,
+                        FileStatus::Failed => Color32::RED,
+                    };
+
+                    row.col(|ui| {
+                        ui.label(RichText::new(file.status.to_string()).color(color));
+                    });
+                    row.col(|ui| {
+                        let output = file
+                            .output
+                            .as_ref()
+                            .map_or(String::new(), |output| output.display().to_string());
+                        ui.label(file.input.display().to_string())
+                            .on_hover_text(format!("-> {}", output));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2?}", file.duration));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!(
+                            "{} -> {}",
+                            format_bytes(file.input_bytes),
+                            format_bytes(file.output_bytes)
+                        ));
+                    });
+                    row.col(|ui| {
+                        let message = match (file.status, file.output_action) {
+                            (FileStatus::Skipped(reason), _) => reason.to_string(),
+                            (_, Some(OutputAction::Renamed)) => format!(
+                                "renamed to {}",
+                                file.output
+                                    .as_ref()
+                                    .and_then(|output| output.file_name())
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                            ),
+                            (_, Some(OutputAction::Overwritten)) => "overwritten".to_string(),
+                            _ => file.error.clone().unwrap_or_default(),
+                        };
+                        ui.label(message);
+                    });
+                });
+            });
+    }
+
     fn toggle_row_selection(&mut self, row_index: usize, row_response: &egui::Response) {
         if row_response.clicked() {
-            if self.selected_row_index == -1 || row_index != self.selected_row_index as usize {
-                self.selected_row_index = row_index as i8;
+            if self.selected_row_index == Some(row_index) {
+                self.selected_row_index = None;
             } else {
-                self.selected_row_index = -1;
+                self.selected_row_index = Some(row_index);
             }
         }
     }
@@ -422,3 +1330,109 @@ impl ImageConverterApp {
         Ok(dimensions)
     }
 }
+
+/// Lossless/lossy combo, with the quality next to it when lossy.
+fn webp_ui(ui: &mut egui::Ui, webp: &mut WebpCompression) {
+    let mut quality = match *webp {
+        WebpCompression::Lossy(quality) => quality,
+        WebpCompression::Lossless => 80,
+    };
+
+    egui::ComboBox::from_label("WebP")
+        .selected_text(match webp {
+            WebpCompression::Lossless => "Lossless",
+            WebpCompression::Lossy(_) => "Lossy",
+        })
+        .show_ui(ui, |ui| {
+            ui.style_mut().wrap = Some(false);
+            ui.set_min_width(80.0);
+
+            ui.selectable_value(webp, WebpCompression::Lossless, "Lossless");
+            ui.selectable_value(webp, WebpCompression::Lossy(quality), "Lossy");
+        });
+
+    if let WebpCompression::Lossy(_) = webp {
+        if ui
+            .add(egui::DragValue::new(&mut quality).clamp_range(0..=100))
+            .on_hover_text("Quality, higher looks better and makes larger files")
+            .changed()
+        {
+            *webp = WebpCompression::Lossy(quality);
+        }
+    }
+}
+
+/// Mipmap policy combo, with the level count next to it when the count is fixed.
+fn mipmaps_ui(ui: &mut egui::Ui, id_source: impl std::hash::Hash, mipmaps: &mut MipmapPolicy) {
+    let mut fixed_count = match *mipmaps {
+        MipmapPolicy::Fixed(count) => count,
+        _ => 4,
+    };
+
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(match mipmaps {
+            MipmapPolicy::Disabled => "None",
+            MipmapPolicy::FullChain => "Full chain",
+            MipmapPolicy::Fixed(_) => "Fixed count",
+        })
+        .show_ui(ui, |ui| {
+            ui.style_mut().wrap = Some(false);
+            ui.set_min_width(80.0);
+
+            ui.selectable_value(mipmaps, MipmapPolicy::Disabled, "None");
+            ui.selectable_value(mipmaps, MipmapPolicy::FullChain, "Full chain");
+            ui.selectable_value(mipmaps, MipmapPolicy::Fixed(fixed_count), "Fixed count");
+        });
+
+    if let MipmapPolicy::Fixed(_) = mipmaps {
+        if ui
+            .add(egui::DragValue::new(&mut fixed_count).clamp_range(1..=16))
+            .on_hover_text("Number of levels, including the full size image")
+            .changed()
+        {
+            *mipmaps = MipmapPolicy::Fixed(fixed_count);
+        }
+    }
+}
+
+fn overwrite_label(policy: OverwritePolicy) -> &'static str {
+    match policy {
+        OverwritePolicy::Overwrite => "Overwrite",
+        OverwritePolicy::SkipExisting => "Skip",
+        OverwritePolicy::SkipIfNewer => "Skip if newer",
+        OverwritePolicy::Rename => "Rename",
+    }
+}
+
+fn glob_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn collision_label(policy: CollisionPolicy) -> &'static str {
+    match policy {
+        CollisionPolicy::Error => "Fail",
+        CollisionPolicy::PreferFormat => "Prefer source format",
+        CollisionPolicy::Suffix => "Add source extension",
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
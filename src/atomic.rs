@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use log::warn;
+
+/// Marks the temporary siblings written by [`write_atomically`].
+const TEMP_MARKER: &str = ".converting";
+
+/// Runs `write` on a temporary sibling of `output` and renames it to `output` once `write`
+/// succeeded, so a failed or interrupted write never leaves a half-written file at `output`.
+///
+/// The temporary file is removed when `write` fails or panics. One left behind by a crash is
+/// ignored when scanning for images and replaced by the next write of `output`.
+pub(crate) fn write_atomically(
+    output: &Path,
+    write: impl FnOnce(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let temp = TempFile(temp_path(output));
+    write(&temp.0)?;
+    fs::rename(&temp.0, output)
+        .with_context(|| format!("failed to move {:?} to {:?}", temp.0, output))
+}
+
+/// `true` for the temporary files of [`write_atomically`], e.g. left behind by a crash.
+pub(crate) fn is_temp_file(path: &Path) -> bool {
+    path.file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with(TEMP_MARKER))
+}
+
+/// `dir/.name.converting.ext`, hidden and keeping the extension the encoder is picked by.
+fn temp_path(output: &Path) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match output.extension() {
+        Some(extension) => format!(".{}{}.{}", stem, TEMP_MARKER, extension.to_string_lossy()),
+        None => format!(".{}{}", stem, TEMP_MARKER),
+    };
+    output.with_file_name(name)
+}
+
+/// Removes the file when dropped.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        match fs::remove_file(&self.0) {
+            Ok(()) => {}
+            // renamed into place, or the writer failed before creating it
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("failed to remove {:?}: {:?}", self.0, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = Path::new("./test_images/atomic_output");
+        fs::create_dir_all(dir).unwrap();
+        let output = dir.join("image.dds");
+        let temp = temp_path(&output);
+        assert_eq!(temp, dir.join(".image.converting.dds"));
+        assert!(is_temp_file(&temp));
+        assert!(!is_temp_file(&output));
+
+        fs::write(&output, "old").unwrap();
+        let failed = write_atomically(&output, |temp| {
+            fs::write(temp, "half")?;
+            anyhow::bail!("encoder failed")
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+        assert!(!temp.exists());
+
+        write_atomically(&output, |temp| Ok(fs::write(temp, "new")?)).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+        assert!(!temp.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, error, warn};
+use pathdiff::diff_paths;
+use serde::{Deserialize, Serialize};
+
+use crate::format::{CacheMode, ConvertOptions, ImageFormatEnum};
+use crate::report::{FileReport, FileStatus};
+
+/// Written to the root of the destination folder.
+pub(crate) const CACHE_FILE_NAME: &str = ".image-converter-cache.json";
+/// Bumped when the manifest layout or the settings hash changes, older manifests are ignored.
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Keyed by the input path relative to the source folder.
+    files: BTreeMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    input_hash: String,
+    settings_hash: String,
+    /// Relative to the destination folder.
+    output: String,
+    output_hash: String,
+}
+
+/// Remembers what every input was converted to, so unchanged inputs are skipped on the next run.
+pub(crate) struct BuildCache {
+    manifest_path: PathBuf,
+    source_dir: PathBuf,
+    output_dir: PathBuf,
+    mode: CacheMode,
+    manifest: Manifest,
+    /// Input and settings hashes of the files about to be converted.
+    pending: HashMap<PathBuf, (String, String)>,
+}
+
+impl BuildCache {
+    /// Returns `None` when the cache is turned off. A missing or unreadable manifest starts empty.
+    pub(crate) fn load(source_dir: &str, output_dir: &str, mode: CacheMode) -> Option<Self> {
+        if mode == CacheMode::Off {
+            return None;
+        }
+
+        let manifest_path = Path::new(output_dir).join(CACHE_FILE_NAME);
+        let manifest = match fs::read_to_string(&manifest_path) {
+            Ok(contents) => serde_json::from_str::<Manifest>(&contents)
+                .ok()
+                .filter(|manifest| manifest.version == MANIFEST_VERSION)
+                .unwrap_or_else(|| {
+                    warn!("ignoring outdated or broken cache {:?}", manifest_path);
+                    Manifest::default()
+                }),
+            Err(_) => Manifest::default(),
+        };
+
+        Some(Self {
+            manifest_path,
+            source_dir: PathBuf::from(source_dir),
+            output_dir: PathBuf::from(output_dir),
+            mode,
+            manifest,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// `true` when `path` was converted with the same bytes and settings and its output is intact.
+    /// Always `false` when rebuilding, the hashes are still kept to update the manifest.
+    pub(crate) fn is_unchanged(&mut self, path: &str, options: &ConvertOptions) -> bool {
+        let (input_hash, settings_hash) =
+            match (hash_file(Path::new(path)), settings_hash(path, options)) {
+                (Ok(input_hash), Ok(settings_hash)) => (input_hash, settings_hash),
+                // unreadable files or invalid rules fail while converting with a better message
+                _ => return false,
+            };
+
+        let unchanged = self.mode == CacheMode::Incremental
+            && self.entry(path).is_some_and(|entry| {
+                entry.input_hash == input_hash
+                    && entry.settings_hash == settings_hash
+                    && hash_file(&self.output_dir.join(&entry.output))
+                        .is_ok_and(|output_hash| output_hash == entry.output_hash)
+            });
+
+        self.pending
+            .insert(PathBuf::from(path), (input_hash, settings_hash));
+        unchanged
+    }
+
+    /// Stores the converted files and forgets the failed ones.
+    pub(crate) fn record(&mut self, files: &[FileReport]) {
+        for file in files {
+            let Some(key) = self.key(&file.input) else {
+                continue;
+            };
+
+            match file.status {
+                FileStatus::Converted => {
+                    let entry = self.pending.remove(&file.input).and_then(|hashes| {
+                        let output = file.output.as_ref()?;
+                        Some(CacheEntry {
+                            input_hash: hashes.0,
+                            settings_hash: hashes.1,
+                            output: relative_key(output, &self.output_dir)?,
+                            output_hash: hash_file(output).ok()?,
+                        })
+                    });
+                    match entry {
+                        Some(entry) => {
+                            self.manifest.files.insert(key, entry);
+                        }
+                        None => {
+                            self.manifest.files.remove(&key);
+                        }
+                    }
+                }
+                FileStatus::Failed => {
+                    self.manifest.files.remove(&key);
+                }
+                FileStatus::Skipped(_) => {}
+            }
+        }
+    }
+
+    /// Errors are only logged, a lost manifest just means converting everything next time.
+    pub(crate) fn save(&mut self) {
+        self.manifest.version = MANIFEST_VERSION;
+        let result = serde_json::to_string_pretty(&self.manifest)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| {
+                fs::create_dir_all(&self.output_dir)?;
+                fs::write(&self.manifest_path, contents)?;
+                Ok(())
+            });
+
+        match result {
+            Ok(()) => debug!("cache saved to {:?}", self.manifest_path),
+            Err(e) => error!("failed to save cache {:?}: {:?}", self.manifest_path, e),
+        }
+    }
+
+    fn entry(&self, path: &str) -> Option<&CacheEntry> {
+        self.manifest.files.get(&self.key(Path::new(path))?)
+    }
+
+    fn key(&self, input: &Path) -> Option<String> {
+        relative_key(input, &self.source_dir)
+    }
+}
+
+/// `path` relative to `dir` with forward slashes, so a manifest works on every platform.
+fn relative_key(path: &Path, dir: &Path) -> Option<String> {
+    let relative = diff_paths(path, dir)?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(fs::File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Hash of every setting that changes the output of `path`.
+fn settings_hash(path: &str, options: &ConvertOptions) -> anyhow::Result<String> {
+    let settings = match options.output_format {
+        format if format.is_gpu_texture() => format!(
+            "{:?} {:?}",
+            options.output_format,
+            options.dds_settings(Path::new(path))?
+        ),
+        ImageFormatEnum::WEBP => format!("{:?} {}", options.output_format, options.webp),
+        format => format!("{:?}", format),
+    };
+    Ok(blake3::hash(settings.as_bytes()).to_hex().to_string())
+}
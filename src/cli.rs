@@ -0,0 +1,557 @@
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use clap::{Args, Parser, Subcommand};
+use log::{error, info};
+
+use image_converter::{
+    convert, plan, scan_image_files, CacheMode, CollisionPolicy, ConversionReport, ConvertOptions,
+    DdsRule, ErrorPolicy, FolderWatcher, ImageFormatEnum, MipmapPolicy, OverwritePolicy, Preset,
+    Presets, ScanOptions, WatchEvent, WebpCompression, DEFAULT_DEBOUNCE,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Simple Image Converter to save your time")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Convert every image in a folder (recursively) without opening the GUI
+    Convert(ConvertArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ConvertArgs {
+    /// Folder to scan for images
+    #[arg(short, long)]
+    pub source: String,
+    /// Folder to write converted images to, created when missing. Defaults to the source folder
+    #[arg(short, long)]
+    pub dest: Option<String>,
+    /// Output format: png, dds, ktx2, tga, jpeg, jpg, webp, bmp, tiff, tif, gif, ico, icns or exr
+    #[arg(short, long, value_parser = ImageFormatEnum::from_str, required_unless_present = "preset")]
+    pub format: Option<ImageFormatEnum>,
+    /// Name of a preset saved in the app or path to an exported preset file.
+    /// The other options override the settings of the preset
+    #[arg(short, long)]
+    pub preset: Option<String>,
+    /// Compression format used when the output format is dds or ktx2 [default: BC1RgbaUnorm]
+    #[arg(long, value_parser = image_dds::ImageFormat::from_str)]
+    pub dds_format: Option<image_dds::ImageFormat>,
+    /// BC compression quality: Fast, Normal or Slow [default: Fast]
+    #[arg(long, value_parser = image_dds::Quality::from_str)]
+    pub quality: Option<image_dds::Quality>,
+    /// Mipmaps of dds and ktx2 output: none, full or a number of levels [default: full]
+    #[arg(long, value_parser = MipmapPolicy::from_str)]
+    pub mipmaps: Option<MipmapPolicy>,
+    /// Compression of webp output: lossless or a quality from 0 to 100 [default: lossless]
+    #[arg(long, value_parser = WebpCompression::from_str)]
+    pub webp_quality: Option<WebpCompression>,
+    /// DDS format for matching files as PATTERN=FORMAT, e.g. `_nrm=BC5RgUnorm` or `*_base.*=BC7RgbaUnormSrgb`.
+    /// Repeat for several rules, the first match wins
+    #[arg(long = "rule", value_parser = parse_dds_rule)]
+    pub rules: Vec<DdsRule>,
+    /// What to do when an output file exists: overwrite, skip-existing, skip-if-newer or rename
+    #[arg(long, default_value = "overwrite", value_parser = OverwritePolicy::from_str)]
+    pub overwrite: OverwritePolicy,
+    /// What to do when several images have the same output, e.g. a.png and a.tga both to a.dds:
+    /// error, prefer-format or suffix
+    #[arg(long, default_value = "error", value_parser = CollisionPolicy::from_str)]
+    pub collision: CollisionPolicy,
+    /// Source formats from most to least preferred with --collision prefer-format, e.g. `png,tga,jpg`
+    #[arg(long, value_delimiter = ',', value_parser = ImageFormatEnum::from_str)]
+    pub prefer: Vec<ImageFormatEnum>,
+    /// Only convert images matching this glob, relative to the source folder, e.g. `textures/**`.
+    /// Repeat for several patterns
+    #[arg(long)]
+    pub include: Vec<String>,
+    /// Leave out images matching this glob, relative to the source folder, e.g. `**/old/**`.
+    /// Repeat for several patterns
+    #[arg(long)]
+    pub exclude: Vec<String>,
+    /// Folder levels to go down into, 1 only converts the images directly in the source folder
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_depth: Option<u64>,
+    /// Also convert files and folders whose name starts with a dot
+    #[arg(long)]
+    pub hidden: bool,
+    /// Convert the images and folders symlinks point to instead of skipping symlinks
+    #[arg(long)]
+    pub follow_symlinks: bool,
+    /// Don't read the .imageconverterignore files of the source folder
+    #[arg(long)]
+    pub no_ignore_file: bool,
+    /// Convert every file, even the ones unchanged since the last conversion
+    #[arg(long)]
+    pub force: bool,
+    /// Convert every file and don't write the cache manifest to the destination folder
+    #[arg(long, conflicts_with = "force")]
+    pub no_cache: bool,
+    /// Print what converting would do (output paths, overwrites, skips and collisions) without writing anything
+    #[arg(long, conflicts_with = "watch")]
+    pub dry_run: bool,
+    /// Keep running after converting and convert images as they are created or modified
+    #[arg(long)]
+    pub watch: bool,
+    /// Milliseconds without writes to wait before converting a changed image in watch mode
+    #[arg(long, default_value_t = DEFAULT_DEBOUNCE.as_millis() as u64)]
+    pub debounce_ms: u64,
+    /// Convert files one by one instead of in parallel
+    #[arg(long)]
+    pub sequential: bool,
+    /// Keep converting the other files when one fails instead of stopping
+    #[arg(long)]
+    pub continue_on_error: bool,
+}
+
+fn parse_dds_rule(rule: &str) -> Result<DdsRule, String> {
+    let Some((pattern, format)) = rule.rsplit_once('=') else {
+        return Err(format!("expected PATTERN=FORMAT, got {:?}", rule));
+    };
+    let format = image_dds::ImageFormat::from_str(format).map_err(|e| e.to_string())?;
+
+    let rule = DdsRule::new(pattern, format);
+    rule.matches(Path::new("")).map_err(|e| e.to_string())?;
+    Ok(rule)
+}
+
+/// Default options, then the preset, then the options given on the command line.
+fn convert_options(args: &ConvertArgs) -> anyhow::Result<ConvertOptions> {
+    let mut options = ConvertOptions::default();
+    if let Some(preset) = &args.preset {
+        load_preset(preset)?.apply(&mut options);
+    }
+
+    if let Some(format) = args.format {
+        options.output_format = format;
+    }
+    if let Some(dds_format) = args.dds_format {
+        options.dds_format = dds_format;
+    }
+    if let Some(quality) = args.quality {
+        options.dds_quality = quality;
+    }
+    if let Some(mipmaps) = args.mipmaps {
+        options.mipmaps = mipmaps;
+    }
+    if let Some(webp) = args.webp_quality {
+        options.webp = webp;
+    }
+
+    // rules given on the command line only pick the format and come before the preset ones
+    let cli_rules = args.rules.iter().map(|rule| DdsRule {
+        quality: options.dds_quality,
+        mipmaps: options.mipmaps,
+        ..rule.clone()
+    });
+    options.dds_rules = cli_rules.chain(options.dds_rules).collect();
+
+    options.overwrite = args.overwrite;
+    options.collision = args.collision;
+    if !args.prefer.is_empty() {
+        options.format_preference.clone_from(&args.prefer);
+    }
+    options.cache = if args.no_cache {
+        CacheMode::Off
+    } else if args.force {
+        CacheMode::Rebuild
+    } else {
+        CacheMode::Incremental
+    };
+    options.use_sequential_convert = args.sequential;
+    options.error_policy = if args.continue_on_error {
+        ErrorPolicy::Continue
+    } else {
+        ErrorPolicy::FailFast
+    };
+    Ok(options)
+}
+
+fn scan_options(args: &ConvertArgs) -> ScanOptions {
+    ScanOptions {
+        include: args.include.clone(),
+        exclude: args.exclude.clone(),
+        max_depth: args.max_depth.map(|depth| depth as usize),
+        include_hidden: args.hidden,
+        follow_symlinks: args.follow_symlinks,
+        use_ignore_file: !args.no_ignore_file,
+    }
+}
+
+/// A file path is imported, anything else is looked up in the presets saved by the app.
+fn load_preset(name_or_path: &str) -> anyhow::Result<Preset> {
+    let path = Path::new(name_or_path);
+    if path.is_file() {
+        return Preset::import(path);
+    }
+
+    let Some(presets_path) = Presets::default_path() else {
+        bail!("no preset file {:?} and no saved presets", name_or_path);
+    };
+    Presets::load(&presets_path)?
+        .get(name_or_path)
+        .cloned()
+        .with_context(|| format!("no preset called {:?}", name_or_path))
+}
+
+/// exit code used when the arguments point to something unusable, same as clap's usage errors
+const EXIT_INVALID_INPUT: u8 = 2;
+
+pub fn run_convert(args: ConvertArgs) -> ExitCode {
+    if !Path::new(&args.source).is_dir() {
+        error!("source folder {:?} does not exist", args.source);
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    }
+    let options = match convert_options(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            error!("{:#}", e);
+            return ExitCode::from(EXIT_INVALID_INPUT);
+        }
+    };
+    let scan = scan_options(&args);
+
+    // missing destination folders are created while converting
+    let dest = args.dest.unwrap_or(args.source.clone());
+
+    let files = match scan_image_files(&args.source, &scan) {
+        Ok(files) => files,
+        Err(e) => {
+            error!("{:#}", e);
+            return ExitCode::from(EXIT_INVALID_INPUT);
+        }
+    };
+    info!("found {} images in {:?}", files.len(), args.source);
+
+    if args.dry_run {
+        println!("{}", plan(&files, &args.source, &dest, &options));
+        return ExitCode::SUCCESS;
+    }
+
+    let report = convert(files, args.source.clone(), dest.clone(), &options);
+    print_report(&report);
+
+    if args.watch {
+        return watch(
+            args.source,
+            dest,
+            options,
+            &scan,
+            Duration::from_millis(args.debounce_ms),
+        );
+    }
+
+    if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Runs until the process is stopped, only returns when watching can't start.
+fn watch(
+    source: String,
+    dest: String,
+    options: ConvertOptions,
+    scan: &ScanOptions,
+    debounce: Duration,
+) -> ExitCode {
+    let (tx, rx) = channel();
+    let _watcher = match FolderWatcher::new(source.clone(), dest, options, scan, debounce, tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            error!("failed to watch {:?}: {:#}", source, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("watching {}, press Ctrl+C to stop", source);
+
+    for event in rx {
+        match event {
+            WatchEvent::Changed(files) => {
+                for file in files {
+                    println!("changed: {}", file.display());
+                }
+            }
+            WatchEvent::Converted(report) => print_report(&report),
+            WatchEvent::Error(e) => eprintln!("watch error: {}", e),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_report(report: &ConversionReport) {
+    for failed in report.failed() {
+        eprintln!(
+            "failed: {} ({})",
+            failed.input.display(),
+            failed.error.as_deref().unwrap_or_default()
+        );
+    }
+    println!("{}", report);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_convert_args() {
+        let cli = Cli::try_parse_from([
+            "image_converter",
+            "convert",
+            "--source",
+            "./test_images",
+            "--format",
+            "DDS",
+            "--dds-format",
+            "BC7RgbaUnormSrgb",
+            "--sequential",
+        ])
+        .unwrap();
+
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected convert subcommand");
+        };
+        assert_eq!(args.source, "./test_images");
+        assert_eq!(args.dest, None);
+        assert_eq!(args.format, Some(ImageFormatEnum::DDS));
+        assert_eq!(
+            args.dds_format,
+            Some(image_dds::ImageFormat::BC7RgbaUnormSrgb)
+        );
+        assert!(args.sequential);
+        assert_eq!(args.quality, None);
+        assert_eq!(args.mipmaps, None);
+    }
+
+    #[test]
+    fn test_parse_quality_and_mipmaps() {
+        let parse = |mipmaps: &str| {
+            Cli::try_parse_from([
+                "image_converter",
+                "convert",
+                "--source",
+                "./test_images",
+                "--format",
+                "dds",
+                "--quality",
+                "Slow",
+                "--mipmaps",
+                mipmaps,
+            ])
+        };
+
+        let Some(Command::Convert(args)) = parse("4").unwrap().command else {
+            panic!("expected convert subcommand");
+        };
+        assert_eq!(args.quality, Some(image_dds::Quality::Slow));
+        assert_eq!(args.mipmaps, Some(MipmapPolicy::Fixed(4)));
+
+        let Some(Command::Convert(args)) = parse("none").unwrap().command else {
+            panic!("expected convert subcommand");
+        };
+        assert_eq!(args.mipmaps, Some(MipmapPolicy::Disabled));
+
+        assert!(parse("0").is_err());
+        assert!(parse("some").is_err());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let cli = Cli::try_parse_from([
+            "image_converter",
+            "convert",
+            "--source",
+            "./test_images",
+            "--format",
+            "dds",
+            "--rule",
+            "_nrm=BC5RgUnorm",
+            "--rule",
+            "*_base.*=BC7RgbaUnormSrgb",
+        ])
+        .unwrap();
+
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected convert subcommand");
+        };
+        assert_eq!(args.rules.len(), 2);
+        assert_eq!(args.rules[0].pattern, "_nrm");
+        assert_eq!(
+            args.rules[1].dds_format,
+            image_dds::ImageFormat::BC7RgbaUnormSrgb
+        );
+        assert!(args.rules[1].srgb);
+
+        assert!(parse_dds_rule("_nrm").is_err());
+        assert!(parse_dds_rule("[_nrm=BC5RgUnorm").is_err());
+    }
+
+    #[test]
+    fn test_parse_collision_policy() {
+        let cli = Cli::try_parse_from([
+            "image_converter",
+            "convert",
+            "--source",
+            "./test_images",
+            "--format",
+            "dds",
+            "--collision",
+            "prefer-format",
+            "--prefer",
+            "tga,png",
+        ])
+        .unwrap();
+
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected convert subcommand");
+        };
+        let options = convert_options(&args).unwrap();
+        assert_eq!(options.collision, CollisionPolicy::PreferFormat);
+        assert_eq!(
+            options.format_preference,
+            [ImageFormatEnum::TGA, ImageFormatEnum::PNG]
+        );
+    }
+
+    #[test]
+    fn test_parse_scan_options() {
+        let cli = Cli::try_parse_from([
+            "image_converter",
+            "convert",
+            "--source",
+            "./test_images",
+            "--format",
+            "dds",
+            "--include",
+            "textures/**",
+            "--exclude",
+            "**/old/**",
+            "--exclude",
+            "*_nrm.*",
+            "--max-depth",
+            "2",
+            "--no-ignore-file",
+        ])
+        .unwrap();
+
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected convert subcommand");
+        };
+        let scan = scan_options(&args);
+        assert_eq!(scan.include, ["textures/**"]);
+        assert_eq!(scan.exclude.len(), 2);
+        assert_eq!(scan.max_depth, Some(2));
+        assert!(!scan.include_hidden);
+        assert!(!scan.use_ignore_file);
+    }
+
+    #[test]
+    fn test_parse_without_subcommand_launches_gui() {
+        let cli = Cli::try_parse_from(["image_converter"]).unwrap();
+
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_format() {
+        let result = Cli::try_parse_from([
+            "image_converter",
+            "convert",
+            "--source",
+            "./test_images",
+            "--format",
+            "psd",
+        ]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_run_convert_missing_source() {
+        let args = ConvertArgs {
+            source: "./does_not_exist".to_string(),
+            dest: None,
+            format: Some(ImageFormatEnum::PNG),
+            preset: None,
+            dds_format: None,
+            quality: None,
+            mipmaps: None,
+            webp_quality: None,
+            rules: Vec::new(),
+            overwrite: OverwritePolicy::Overwrite,
+            collision: CollisionPolicy::Error,
+            prefer: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: None,
+            hidden: false,
+            follow_symlinks: false,
+            no_ignore_file: false,
+            force: false,
+            no_cache: false,
+            dry_run: false,
+            watch: false,
+            debounce_ms: 500,
+            sequential: true,
+            continue_on_error: false,
+        };
+
+        assert_eq!(run_convert(args), ExitCode::from(EXIT_INVALID_INPUT));
+    }
+
+    #[test]
+    fn test_preset_file_with_overrides() {
+        let preset_path = "./test_images/cli_preset.toml";
+        Preset {
+            name: "textures".to_string(),
+            output_format: ImageFormatEnum::DDS,
+            dds_quality: image_dds::Quality::Slow,
+            dds_rules: vec![DdsRule::new("_nrm", image_dds::ImageFormat::BC5RgUnorm)],
+            ..Default::default()
+        }
+        .export(Path::new(preset_path))
+        .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "image_converter",
+            "convert",
+            "--source",
+            "./test_images",
+            "--preset",
+            preset_path,
+            "--quality",
+            "Normal",
+            "--rule",
+            "_spec=BC4RUnorm",
+        ])
+        .unwrap();
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected convert subcommand");
+        };
+        let options = convert_options(&args).unwrap();
+        fs::remove_file(preset_path).unwrap();
+
+        assert_eq!(options.output_format, ImageFormatEnum::DDS);
+        assert_eq!(options.dds_quality, image_dds::Quality::Normal);
+        assert_eq!(options.dds_rules.len(), 2);
+        assert_eq!(options.dds_rules[0].pattern, "_spec");
+        assert_eq!(options.dds_rules[0].quality, image_dds::Quality::Normal);
+
+        let missing = ConvertArgs {
+            preset: Some("does not exist".to_string()),
+            ..args
+        };
+        assert!(convert_options(&missing).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::format::{CollisionPolicy, ConvertOptions, ImageFormatEnum};
+
+/// What happens to an input whose output path is shared with other inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Resolution {
+    /// Fail with this message, see [`CollisionPolicy::Error`].
+    Fail(String),
+    /// Another input is preferred, see [`CollisionPolicy::PreferFormat`].
+    Skip,
+    /// Write to this path instead, see [`CollisionPolicy::Suffix`].
+    Rename(PathBuf),
+}
+
+/// Finds the inputs of `outputs` (input path, output path) that write the same output and
+/// resolves them with `options.collision`. Inputs without a collision are left out.
+///
+/// Outputs differing only in case collide too, they are the same file on Windows and macOS.
+pub(crate) fn resolve_collisions(
+    outputs: &[(String, PathBuf)],
+    options: &ConvertOptions,
+) -> HashMap<String, Resolution> {
+    let mut groups: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut output_of: HashMap<&str, &Path> = HashMap::new();
+    for (input, output) in outputs {
+        let key = output.to_string_lossy().to_lowercase();
+        groups.entry(key).or_default().push(input);
+        output_of.insert(input, output);
+    }
+
+    let mut resolutions = HashMap::new();
+    for inputs in groups.into_values().filter(|inputs| inputs.len() > 1) {
+        match options.collision {
+            CollisionPolicy::Error => {
+                for input in &inputs {
+                    let output = output_of[input];
+                    let others: Vec<&str> = inputs
+                        .iter()
+                        .copied()
+                        .filter(|other| other != input)
+                        .collect();
+                    let message = format!(
+                        "{} is also the output of {}",
+                        output.display(),
+                        others.join(", ")
+                    );
+                    resolutions.insert(input.to_string(), Resolution::Fail(message));
+                }
+            }
+            CollisionPolicy::PreferFormat => {
+                // min_by_key keeps the first input on ties
+                let preferred = inputs
+                    .iter()
+                    .min_by_key(|input| preference_rank(input, &options.format_preference))
+                    .copied();
+                for input in inputs.iter().filter(|input| Some(**input) != preferred) {
+                    resolutions.insert(input.to_string(), Resolution::Skip);
+                }
+            }
+            CollisionPolicy::Suffix => {
+                for input in &inputs {
+                    resolutions.insert(
+                        input.to_string(),
+                        Resolution::Rename(suffixed_path(output_of[input], input)),
+                    );
+                }
+            }
+        }
+    }
+    resolutions
+}
+
+/// Position of the format of `input` in `preference`, unlisted formats come last.
+fn preference_rank(input: &str, preference: &[ImageFormatEnum]) -> usize {
+    Path::new(input)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| ImageFormatEnum::from_str(extension).ok())
+        .and_then(|format| preference.iter().position(|preferred| *preferred == format))
+        .unwrap_or(preference.len())
+}
+
+/// `foo.dds` becomes `foo_png.dds` for the input `foo.png`.
+fn suffixed_path(output: &Path, input: &str) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let source_extension = Path::new(input)
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    let extension = output.extension().unwrap_or_default().to_string_lossy();
+
+    output.with_file_name(format!("{}_{}.{}", stem, source_extension, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colliding_outputs() -> Vec<(String, PathBuf)> {
+        vec![
+            ("src/foo.jpg".to_string(), PathBuf::from("out/foo.dds")),
+            ("src/foo.tga".to_string(), PathBuf::from("out/foo.dds")),
+            ("src/bar.png".to_string(), PathBuf::from("out/bar.dds")),
+        ]
+    }
+
+    #[test]
+    fn test_resolve_collisions() {
+        let resolve = |collision| {
+            resolve_collisions(
+                &colliding_outputs(),
+                &ConvertOptions {
+                    collision,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let failed = resolve(CollisionPolicy::Error);
+        assert_eq!(failed.len(), 2);
+        assert!(matches!(&failed["src/foo.jpg"], Resolution::Fail(e) if e.contains("src/foo.tga")));
+
+        // tga comes before jpg in the default preference
+        let preferred = resolve(CollisionPolicy::PreferFormat);
+        assert_eq!(preferred.len(), 1);
+        assert_eq!(preferred["src/foo.jpg"], Resolution::Skip);
+
+        let suffixed = resolve(CollisionPolicy::Suffix);
+        assert_eq!(
+            suffixed["src/foo.tga"],
+            Resolution::Rename(PathBuf::from("out/foo_tga.dds"))
+        );
+        assert!(!suffixed.contains_key("src/bar.png"));
+    }
+
+    #[test]
+    fn test_resolve_case_only_collisions() {
+        let outputs = vec![
+            ("src/Sub/a.png".to_string(), PathBuf::from("out/Sub/a.dds")),
+            ("src/sub/a.png".to_string(), PathBuf::from("out/sub/a.dds")),
+            ("src/Tex.png".to_string(), PathBuf::from("out/Tex.dds")),
+            ("src/tex.tga".to_string(), PathBuf::from("out/tex.dds")),
+        ];
+
+        let failed = resolve_collisions(&outputs, &ConvertOptions::default());
+
+        assert_eq!(failed.len(), 4);
+        assert!(matches!(&failed["src/Tex.png"], Resolution::Fail(e) if e.contains("src/tex.tga")));
+    }
+}
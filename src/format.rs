@@ -0,0 +1,361 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use strum::{Display, EnumIter, EnumString, IntoStaticStr};
+
+use crate::rules::DdsRule;
+
+/// Image formats the converter reads and writes.
+///
+/// The strum serialization is used as the file extension of converted images.
+#[derive(
+    Debug, PartialEq, Clone, EnumString, IntoStaticStr, EnumIter, Copy, Serialize, Deserialize,
+)]
+#[strum(ascii_case_insensitive)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ImageFormatEnum {
+    #[strum(serialize = "png")]
+    PNG,
+    #[strum(serialize = "dds")]
+    DDS,
+    #[strum(serialize = "tga")]
+    TGA,
+    #[strum(serialize = "JPEG")]
+    JPEG,
+    #[strum(serialize = "JPG")]
+    JPG,
+    #[strum(serialize = "webp")]
+    WEBP,
+    #[strum(serialize = "bmp")]
+    BMP,
+    #[strum(serialize = "tiff")]
+    TIFF,
+    #[strum(serialize = "tif")]
+    TIF,
+    /// Only the first frame of an animated GIF is read.
+    #[strum(serialize = "gif")]
+    GIF,
+    /// Windows icon with every [`crate::ICON_SIZES`] size up to the size of the source.
+    #[strum(serialize = "ico")]
+    ICO,
+    /// macOS icon with every [`crate::ICON_SIZES`] size up to the size of the source.
+    #[strum(serialize = "icns")]
+    ICNS,
+    /// OpenEXR, keeps the float data of HDR sources.
+    #[strum(serialize = "exr")]
+    EXR,
+    /// GPU texture in the Khronos container, written with the same settings as DDS.
+    #[strum(serialize = "ktx2")]
+    KTX2,
+}
+
+impl ImageFormatEnum {
+    /// DDS and KTX2, encoded with the DDS format, quality, mipmaps and rules of
+    /// [`ConvertOptions`].
+    pub fn is_gpu_texture(self) -> bool {
+        matches!(self, ImageFormatEnum::DDS | ImageFormatEnum::KTX2)
+    }
+}
+
+/// What to do with the remaining files once one fails to convert.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ErrorPolicy {
+    /// Stop at the first failure. Files not started yet are reported as skipped.
+    #[default]
+    FailFast,
+    /// Convert every file and collect all failures in the report.
+    Continue,
+}
+
+/// What to do when the output file of an image already exists.
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Default,
+    Display,
+    EnumString,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverwritePolicy {
+    /// Replace the existing file.
+    #[default]
+    Overwrite,
+    /// Keep the existing file and skip the image.
+    SkipExisting,
+    /// Skip the image when the existing file was modified after it, convert it otherwise.
+    SkipIfNewer,
+    /// Keep the existing file and write to `name_1.ext`, `name_2.ext`, ... instead.
+    Rename,
+}
+
+/// What to do when several inputs have the same output path, e.g. `foo.png` and `foo.tga`
+/// both converted to `foo.dds`.
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Default,
+    Display,
+    EnumString,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+#[serde(rename_all = "kebab-case")]
+pub enum CollisionPolicy {
+    /// Fail all the colliding inputs.
+    #[default]
+    Error,
+    /// Convert the input whose format comes first in [`ConvertOptions::format_preference`]
+    /// and skip the others.
+    PreferFormat,
+    /// Convert every input, adding its source extension to the name: `foo_png.dds`, `foo_tga.dds`.
+    Suffix,
+}
+
+/// Whether files converted by an earlier run are converted again, see [`SkipReason::Unchanged`](crate::SkipReason::Unchanged).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum CacheMode {
+    /// Convert every file and leave no cache behind.
+    #[default]
+    Off,
+    /// Skip files whose content and settings did not change since they were converted.
+    /// The hashes are kept in a manifest at the root of the destination folder.
+    Incremental,
+    /// Convert every file and refresh the manifest.
+    Rebuild,
+}
+
+/// How many mipmaps are generated for DDS output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum MipmapPolicy {
+    /// Only the full size image.
+    Disabled,
+    /// Every level down to 1x1.
+    #[default]
+    FullChain,
+    /// At most this many levels, including the full size image.
+    Fixed(u32),
+}
+
+impl MipmapPolicy {
+    /// Mipmaps to generate for an image of this size, a fixed count is capped at the full chain.
+    pub(crate) fn to_mipmaps(self, width: u32, height: u32) -> image_dds::Mipmaps {
+        match self {
+            MipmapPolicy::Disabled => image_dds::Mipmaps::Disabled,
+            MipmapPolicy::FullChain => image_dds::Mipmaps::GeneratedAutomatic,
+            MipmapPolicy::Fixed(count) => {
+                let full_chain = u32::BITS - width.max(height).max(1).leading_zeros();
+                image_dds::Mipmaps::GeneratedExact(count.min(full_chain))
+            }
+        }
+    }
+}
+
+impl fmt::Display for MipmapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MipmapPolicy::Disabled => write!(f, "none"),
+            MipmapPolicy::FullChain => write!(f, "full"),
+            MipmapPolicy::Fixed(count) => write!(f, "{}", count),
+        }
+    }
+}
+
+/// Parses `none`, `full` or a level count.
+impl FromStr for MipmapPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(MipmapPolicy::Disabled),
+            "full" => Ok(MipmapPolicy::FullChain),
+            count => match count.parse::<u32>() {
+                Ok(0) => anyhow::bail!("mipmap count must be at least 1"),
+                Ok(count) => Ok(MipmapPolicy::Fixed(count)),
+                Err(_) => anyhow::bail!("expected none, full or a mipmap count, got {:?}", s),
+            },
+        }
+    }
+}
+
+/// Written like the `--mipmaps` option, e.g. `mipmaps = "full"`.
+impl Serialize for MipmapPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MipmapPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let policy = String::deserialize(deserializer)?;
+        policy.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Compression of WebP output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum WebpCompression {
+    /// Keeps every pixel as is.
+    #[default]
+    Lossless,
+    /// Quality from 0 (smallest file) to 100 (best looking).
+    Lossy(u8),
+}
+
+impl fmt::Display for WebpCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebpCompression::Lossless => write!(f, "lossless"),
+            WebpCompression::Lossy(quality) => write!(f, "{}", quality),
+        }
+    }
+}
+
+/// Parses `lossless` or a quality from 0 to 100.
+impl FromStr for WebpCompression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("lossless") {
+            return Ok(WebpCompression::Lossless);
+        }
+        match s.parse::<u8>() {
+            Ok(quality) if quality <= 100 => Ok(WebpCompression::Lossy(quality)),
+            _ => anyhow::bail!("expected lossless or a quality from 0 to 100, got {:?}", s),
+        }
+    }
+}
+
+/// A quality or `lossless`, e.g. `webp = "80"`.
+impl Serialize for WebpCompression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for WebpCompression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let compression = String::deserialize(deserializer)?;
+        compression.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Settings shared by every file of a conversion.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    /// Format every image is converted to.
+    pub output_format: ImageFormatEnum,
+    /// Compression format used when `output_format` is [`ImageFormatEnum::DDS`] or
+    /// [`ImageFormatEnum::KTX2`].
+    pub dds_format: image_dds::ImageFormat,
+    /// Compression effort for BC formats, slower gives better looking results.
+    pub dds_quality: image_dds::Quality,
+    /// Mipmaps generated for DDS and KTX2 output.
+    pub mipmaps: MipmapPolicy,
+    /// Per-file overrides of the DDS settings above, the first matching rule is used.
+    pub dds_rules: Vec<DdsRule>,
+    /// Compression used when `output_format` is [`ImageFormatEnum::WEBP`].
+    pub webp: WebpCompression,
+    /// What happens when the output file already exists.
+    pub overwrite: OverwritePolicy,
+    /// What happens when several inputs would write the same output, like `foo.png` and `foo.tga`.
+    pub collision: CollisionPolicy,
+    /// Source formats from most to least preferred, for [`CollisionPolicy::PreferFormat`].
+    pub format_preference: Vec<ImageFormatEnum>,
+    /// Whether unchanged files of an earlier run are skipped.
+    pub cache: CacheMode,
+    /// Convert files one by one instead of in parallel on the rayon thread pool.
+    pub use_sequential_convert: bool,
+    /// Whether the remaining files are still converted after one fails.
+    pub error_policy: ErrorPolicy,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            output_format: ImageFormatEnum::PNG,
+            dds_format: image_dds::ImageFormat::BC1RgbaUnorm,
+            dds_quality: image_dds::Quality::Fast,
+            mipmaps: MipmapPolicy::default(),
+            dds_rules: Vec::new(),
+            webp: WebpCompression::default(),
+            overwrite: OverwritePolicy::default(),
+            collision: CollisionPolicy::default(),
+            // lossless formats first
+            format_preference: vec![
+                ImageFormatEnum::PNG,
+                ImageFormatEnum::TIFF,
+                ImageFormatEnum::TIF,
+                ImageFormatEnum::EXR,
+                ImageFormatEnum::TGA,
+                ImageFormatEnum::BMP,
+                ImageFormatEnum::DDS,
+                ImageFormatEnum::WEBP,
+                ImageFormatEnum::GIF,
+                ImageFormatEnum::ICO,
+                ImageFormatEnum::JPEG,
+                ImageFormatEnum::JPG,
+            ],
+            cache: CacheMode::default(),
+            use_sequential_convert: false,
+            error_policy: ErrorPolicy::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_round_trip() {
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            mipmaps: MipmapPolicy::Fixed(3),
+            dds_rules: vec![DdsRule::new("_nrm", image_dds::ImageFormat::BC5RgUnorm)],
+            webp: WebpCompression::Lossy(80),
+            error_policy: ErrorPolicy::Continue,
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains(r#""mipmaps":"3""#));
+        assert!(json.contains(r#""webp":"80""#));
+        assert_eq!(
+            serde_json::from_str::<ConvertOptions>(&json).unwrap(),
+            options
+        );
+
+        // settings saved by an older version are missing the newer fields
+        let old: ConvertOptions = serde_json::from_str(r#"{"output_format":"TGA"}"#).unwrap();
+        assert_eq!(old.output_format, ImageFormatEnum::TGA);
+        assert_eq!(old.dds_format, image_dds::ImageFormat::BC1RgbaUnorm);
+    }
+
+    #[test]
+    fn test_parse_webp_compression() {
+        assert_eq!(
+            "Lossless".parse::<WebpCompression>().unwrap(),
+            WebpCompression::Lossless
+        );
+        assert_eq!(
+            "75".parse::<WebpCompression>().unwrap(),
+            WebpCompression::Lossy(75)
+        );
+        assert!("101".parse::<WebpCompression>().is_err());
+        assert!("best".parse::<WebpCompression>().is_err());
+    }
+}
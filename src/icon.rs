@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use icns::{IconFamily, IconType, PixelFormat};
+use image_dds::image::codecs::ico::{IcoEncoder, IcoFrame};
+use image_dds::image::imageops::{self, FilterType};
+use image_dds::image::{ColorType, DynamicImage, RgbaImage};
+use log::debug;
+
+/// Sizes in pixels of the images in ICO and ICNS outputs.
+pub const ICON_SIZES: [u32; 6] = [16, 32, 48, 64, 128, 256];
+
+/// Writes `image` as a Windows icon with one PNG compressed entry per icon size.
+pub(crate) fn save_ico(image: &DynamicImage, output: &Path) -> anyhow::Result<()> {
+    let icons = icon_images(image);
+    let frames = icons
+        .iter()
+        .map(|icon| IcoFrame::as_png(icon.as_raw(), icon.width(), icon.height(), ColorType::Rgba8))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    IcoEncoder::new(&mut writer).encode_images(&frames)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes `image` as a macOS icon with one entry per icon size.
+pub(crate) fn save_icns(image: &DynamicImage, output: &Path) -> anyhow::Result<()> {
+    let mut family = IconFamily::new();
+    for icon in icon_images(image) {
+        let size = icon.width();
+        let icon_type = match size {
+            16 => IconType::RGBA32_16x16,
+            32 => IconType::RGBA32_32x32,
+            // there is no 48 pixel type with alpha, the mask is added with it
+            48 => IconType::RGB24_48x48,
+            64 => IconType::RGBA32_64x64,
+            128 => IconType::RGBA32_128x128,
+            256 => IconType::RGBA32_256x256,
+            _ => anyhow::bail!("no ICNS icon type for {} pixels", size),
+        };
+        let icon = icns::Image::from_data(PixelFormat::RGBA, size, size, icon.into_raw())?;
+        family
+            .add_icon_with_type(&icon, icon_type)
+            .with_context(|| format!("failed to encode the {} pixel icon", size))?;
+    }
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    family.write(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `image` resampled from the full resolution to every icon size up to its longest side, so
+/// small sources are not blown up. Sources that are not square are centered on a transparent
+/// square.
+fn icon_images(image: &DynamicImage) -> Vec<RgbaImage> {
+    let longest_side = image.width().max(image.height());
+    let mut sizes: Vec<u32> = ICON_SIZES
+        .into_iter()
+        .filter(|&size| size <= longest_side)
+        .collect();
+    if sizes.is_empty() {
+        sizes.push(ICON_SIZES[0]);
+    }
+    if sizes.len() < ICON_SIZES.len() {
+        debug!(
+            "{}x{} image, leaving out the larger icon sizes",
+            image.width(),
+            image.height()
+        );
+    }
+
+    sizes
+        .into_iter()
+        .map(|size| {
+            let resized = image.resize(size, size, FilterType::Lanczos3).to_rgba8();
+            let mut icon = RgbaImage::new(size, size);
+            let x = (size - resized.width()) / 2;
+            let y = (size - resized.height()) / 2;
+            imageops::overlay(&mut icon, &resized, x.into(), y.into());
+            icon
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_save_icons() {
+        let output_dir = Path::new("./test_images/icon_output");
+        fs::create_dir_all(output_dir).unwrap();
+        let image = image_dds::image::open("./test_images/o-a_base.png").unwrap();
+        let small = DynamicImage::ImageRgba8(RgbaImage::new(40, 20));
+
+        let ico = output_dir.join("icon.ico");
+        let small_ico = output_dir.join("small.ico");
+        let icns = output_dir.join("icon.icns");
+        save_ico(&image, &ico).unwrap();
+        save_ico(&small, &small_ico).unwrap();
+        save_icns(&image, &icns).unwrap();
+
+        // entry count, then a 16 byte entry per image starting with the width (0 for 256)
+        let ico_sizes = |path: &Path| {
+            let bytes = fs::read(path).unwrap();
+            let count = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+            (0..count)
+                .map(|i| match bytes[6 + i * 16] {
+                    0 => 256,
+                    width => u32::from(width),
+                })
+                .collect::<Vec<_>>()
+        };
+        let ico_entries = ico_sizes(&ico);
+        let small_entries = ico_sizes(&small_ico);
+        let decoded = image_dds::image::open(&ico).unwrap();
+        let family = IconFamily::read(File::open(&icns).unwrap()).unwrap();
+        fs::remove_dir_all(output_dir).unwrap();
+
+        assert_eq!(ico_entries, ICON_SIZES);
+        assert_eq!(small_entries, [16, 32]);
+        assert_eq!(decoded.width(), 256);
+        assert_eq!(family.available_icons().len(), ICON_SIZES.len());
+    }
+}
@@ -1,235 +1,797 @@
-use std::path::Path;
-
-use crate::app::ImageFormatEnum;
-use image_dds::ddsfile;
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::Instant;
+
+use crate::atomic::write_atomically;
+use crate::cache::BuildCache;
+use crate::cancel::CancellationToken;
+use crate::collision::{resolve_collisions, Resolution};
+use crate::format::{
+    ConvertOptions, ErrorPolicy, ImageFormatEnum, OverwritePolicy, WebpCompression,
+};
+use crate::icon::{save_icns, save_ico};
+use crate::ktx2::write_ktx2;
+use crate::progress::{ConvertEvent, ProgressTracker};
+use crate::report::{ConversionReport, FileReport, FileStatus, OutputAction, SkipReason};
+use anyhow::Context;
+use image_dds::image::codecs::hdr::HdrDecoder;
+use image_dds::image::{DynamicImage, Rgb32FImage};
+use image_dds::{ddsfile, SurfaceRgba32Float, SurfaceRgba8};
 use log::{debug, error, info};
 use pathdiff::diff_paths;
 use rayon::prelude::*;
 
+const SUPPORTED_EXTENSIONS: [&str; 13] = [
+    "dds", "png", "jpg", "jpeg", "tga", "webp", "bmp", "tiff", "tif", "gif", "ico", "hdr", "exr",
+];
+
+/// DDS files start with these bytes.
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+/// Radiance HDR files start with `#?RADIANCE` or `#?RGBE`.
+const RADIANCE_MAGIC: &[u8; 2] = b"#?";
+
+/// `true` for `Texture.PNG` as well as `texture.png`.
+pub(crate) fn has_image_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        SUPPORTED_EXTENSIONS
+            .iter()
+            .any(|supported| extension.eq_ignore_ascii_case(supported))
+    })
+}
+
+/// `true` when the extension of `path` is the one of `format`, in any case.
+fn has_format_extension(path: &Path, format: ImageFormatEnum) -> bool {
+    let format_extension: &str = format.into();
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(format_extension))
+}
+
+/// Convert `files` to `options.output_format`, writing each output to the same path relative
+/// to `output_path` as its source has relative to `source_dir`.
+///
+/// Files that already have the output format's extension or no longer exist are skipped.
+/// What happens after a file fails depends on `options.error_policy`.
 pub fn convert(
     files: Vec<String>,
     source_dir: String,
     output_path: String,
-    output_format: ImageFormatEnum,
-    dds_format: image_dds::ImageFormat,
-    use_sequential_convert: bool,
-) -> i8 {
-    let output_format_string: &str = output_format.into();
-    let files: Vec<String> = files
+    options: &ConvertOptions,
+) -> ConversionReport {
+    convert_files(
+        files,
+        source_dir,
+        output_path,
+        options,
+        None,
+        CancellationToken::new(),
+    )
+}
+
+/// Same as [`convert`], sending a [`ConvertEvent`] to `events` whenever a file starts or finishes.
+///
+/// Cancelling `cancel` stops the conversion before the next file; outputs already written
+/// are kept and the files that were not started are reported as skipped.
+pub fn convert_with_progress(
+    files: Vec<String>,
+    source_dir: String,
+    output_path: String,
+    options: &ConvertOptions,
+    events: Sender<ConvertEvent>,
+    cancel: CancellationToken,
+) -> ConversionReport {
+    convert_files(
+        files,
+        source_dir,
+        output_path,
+        options,
+        Some(events),
+        cancel,
+    )
+}
+
+fn convert_files(
+    files: Vec<String>,
+    source_dir: String,
+    output_path: String,
+    options: &ConvertOptions,
+    events: Option<Sender<ConvertEvent>>,
+    cancel: CancellationToken,
+) -> ConversionReport {
+    let start = Instant::now();
+    let progress = ProgressTracker::new(events, cancel, files.len());
+    let mut cache = BuildCache::load(&source_dir, &output_path, options.cache);
+    // the first index of each input, duplicates are reported next to it
+    let input_order: HashMap<PathBuf, usize> = files
         .iter()
-        .filter(|path| !path.ends_with(format!(".{}", output_format_string).as_str())) // to prevent processing same image format, filter out from files
-        .filter(|path| Path::new(path).exists()) // filter out non exist files
-        .cloned()
+        .enumerate()
+        .rev()
+        .map(|(index, path)| (PathBuf::from(path), index))
         .collect();
 
-    return match output_format {
-        ImageFormatEnum::DDS => {
-            let convert_result = if use_sequential_convert {
-                images_to_dds_sequential(files, source_dir, output_path, dds_format)
+    let mut reports = vec![];
+    let mut files_to_convert = vec![];
+    for path in files {
+        match skip_reason(&path, options, cache.as_mut()) {
+            Some(reason) => reports.push(skip_file(&path, reason, &progress)),
+            None => files_to_convert.push(path),
+        }
+    }
+
+    let mut renamed = HashMap::new();
+    // a failed collision stops the other files too when failing fast
+    let mut stopped = false;
+    let mut resolutions =
+        collision_resolutions(&files_to_convert, &source_dir, &output_path, options);
+    files_to_convert.retain(|path| match resolutions.remove(path) {
+        None => true,
+        Some(Resolution::Rename(output)) => {
+            renamed.insert(path.clone(), output);
+            true
+        }
+        Some(Resolution::Skip) => {
+            reports.push(skip_file(path, SkipReason::Collision, &progress));
+            false
+        }
+        Some(Resolution::Fail(message)) => {
+            let report = fail_file(path, message, &progress);
+            stopped |= stops_conversion(&report, options);
+            reports.push(report);
+            false
+        }
+    });
+    if stopped {
+        reports.extend(
+            files_to_convert
+                .drain(..)
+                .filter_map(|path| skip_unstarted_file(&path, stopped, &progress)),
+        );
+    }
+
+    let converted = match options.output_format {
+        format if format.is_gpu_texture() => {
+            if options.use_sequential_convert {
+                images_to_textures_sequential(
+                    files_to_convert,
+                    source_dir,
+                    output_path,
+                    options,
+                    &renamed,
+                    &progress,
+                )
             } else {
-                images_to_dds_parallel(files, source_dir, output_path, dds_format)
-            };
-            if convert_result.is_err() {
-                error!("convert failed {:?}", convert_result.err());
-                return -1;
+                images_to_textures_parallel(
+                    files_to_convert,
+                    source_dir,
+                    output_path,
+                    options,
+                    &renamed,
+                    &progress,
+                )
             }
-            convert_result.unwrap() as i8
         }
         _ => {
-            let convert_result = if use_sequential_convert {
-                images_to_images_sequential(files, source_dir, output_path, output_format)
+            if options.use_sequential_convert {
+                images_to_images_sequential(
+                    files_to_convert,
+                    source_dir,
+                    output_path,
+                    options,
+                    &renamed,
+                    &progress,
+                )
             } else {
-                images_to_images_parallel(files, source_dir, output_path, output_format)
-            };
-            if convert_result.is_err() {
-                error!("convert failed {:?}", convert_result.err());
-                return -1;
+                images_to_images_parallel(
+                    files_to_convert,
+                    source_dir,
+                    output_path,
+                    options,
+                    &renamed,
+                    &progress,
+                )
             }
-            convert_result.unwrap() as i8
         }
     };
+    for failed in converted
+        .iter()
+        .filter(|file| file.status == FileStatus::Failed)
+    {
+        error!("convert failed {:?}: {:?}", failed.input, failed.error);
+    }
+    if let Some(cache) = &mut cache {
+        cache.record(&converted);
+        cache.save();
+    }
+    reports.extend(converted);
+    // skipped files are reported before the converted ones, put them back in input order
+    reports.sort_by_key(|report| input_order.get(&report.input).copied());
+
+    let report = ConversionReport {
+        files: reports,
+        duration: start.elapsed(),
+        cancelled: progress.is_cancelled(),
+    };
+    progress.finished(&report);
+
+    report
 }
 
-fn images_to_dds_sequential(
+fn images_to_textures_sequential(
     files: Vec<String>,
     source_dir: String,
     output_path: String,
-    dds_format: image_dds::ImageFormat,
-) -> anyhow::Result<usize> {
+    options: &ConvertOptions,
+    renamed: &HashMap<String, PathBuf>,
+    progress: &ProgressTracker,
+) -> Vec<FileReport> {
     info!("converting start");
 
-    let files_size = files.len();
+    let mut stopped = false;
+    let reports: Vec<FileReport> = files
+        .iter()
+        .map(|path_string| {
+            if let Some(report) = skip_unstarted_file(path_string, stopped, progress) {
+                return report;
+            }
+            let report = convert_file(
+                path_string,
+                output_for(
+                    path_string,
+                    &source_dir,
+                    &output_path,
+                    options.output_format.into(),
+                    renamed,
+                ),
+                options.overwrite,
+                progress,
+                |output| image_to_texture(path_string, output, options),
+            );
+            stopped = stops_conversion(&report, options);
+            report
+        })
+        .collect();
+    info!("converting ended. total files: {}", reports.len());
 
-    for path_string in files {
-        let cloned_path = path_string.clone();
-        // in order to support processing directory recursive, get diff between current path and source path
-        let mut source_relative_path =
-            diff_paths(Path::new(&cloned_path), Path::new(&source_dir.clone())).ok_or_else(
-                || anyhow::anyhow!("Failed to compute relative path for {}", cloned_path),
-            )?;
+    reports
+}
 
-        let image = image_dds::image::open(Path::new(&path_string))?;
-        let rgba_image = image.to_rgba8();
+fn images_to_textures_parallel(
+    files: Vec<String>,
+    source_dir: String,
+    output_path: String,
+    options: &ConvertOptions,
+    renamed: &HashMap<String, PathBuf>,
+    progress: &ProgressTracker,
+) -> Vec<FileReport> {
+    info!("converting start");
 
-        let dds = image_dds::dds_from_image(
-            &rgba_image,
-            dds_format,
-            image_dds::Quality::Fast,
-            image_dds::Mipmaps::GeneratedAutomatic,
-        )?;
+    let stopped = AtomicBool::new(false);
 
-        source_relative_path.set_extension("dds");
-        let output_path = Path::new(&output_path).join(source_relative_path);
-        let mut writer = std::io::BufWriter::new(std::fs::File::create(&output_path)?);
-        dds.write(&mut writer)?;
-        debug!("{:?} created", output_path);
-    }
-    info!("converting ended. total files: {}", files_size);
+    // Use par_chunks to process files in parallel batches
+    let reports: Vec<FileReport> = files
+        .par_chunks(5)
+        .flat_map_iter(|chunk| {
+            chunk.iter().map(|path_string| {
+                if let Some(report) =
+                    skip_unstarted_file(path_string, stopped.load(Ordering::Relaxed), progress)
+                {
+                    return report;
+                }
+                let report = convert_file(
+                    path_string,
+                    output_for(
+                        path_string,
+                        &source_dir,
+                        &output_path,
+                        options.output_format.into(),
+                        renamed,
+                    ),
+                    options.overwrite,
+                    progress,
+                    |output| image_to_texture(path_string, output, options),
+                );
+                if stops_conversion(&report, options) {
+                    stopped.store(true, Ordering::Relaxed);
+                }
+                report
+            })
+        })
+        .collect();
+    info!("converting ended. total files: {}", reports.len());
 
-    Ok(files_size)
+    reports
 }
 
-fn images_to_dds_parallel(
+fn images_to_images_sequential(
     files: Vec<String>,
     source_dir: String,
     output_path: String,
-    dds_format: image_dds::ImageFormat,
-) -> anyhow::Result<usize> {
+    options: &ConvertOptions,
+    renamed: &HashMap<String, PathBuf>,
+    progress: &ProgressTracker,
+) -> Vec<FileReport> {
     info!("converting start");
 
-    let files_size = files.len();
+    let output_format_string: &str = options.output_format.into();
+    let mut stopped = false;
+    let reports: Vec<FileReport> = files
+        .iter()
+        .map(|path_string| {
+            if let Some(report) = skip_unstarted_file(path_string, stopped, progress) {
+                return report;
+            }
+            let report = convert_file(
+                path_string,
+                output_for(
+                    path_string,
+                    &source_dir,
+                    &output_path,
+                    output_format_string,
+                    renamed,
+                ),
+                options.overwrite,
+                progress,
+                |output| image_to_image(path_string, output, options),
+            );
+            stopped = stops_conversion(&report, options);
+            report
+        })
+        .collect();
+    info!("converting ended. total files: {}", reports.len());
 
-    // Use par_chunks to process files in parallel batches
-    files
-        .par_chunks(5)
-        .try_for_each(|chunk| -> anyhow::Result<()> {
-            chunk
-                .iter()
-                .try_for_each(|path_string| -> anyhow::Result<()> {
-                    let cloned_path = path_string.clone();
-                    let mut source_relative_path = diff_paths(
-                        Path::new(&cloned_path),
-                        Path::new(&source_dir),
-                    )
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("Failed to compute relative path for {}", cloned_path)
-                    })?;
-
-                    let image = image_dds::image::open(Path::new(&path_string))?;
-                    let rgba_image = image.to_rgba8();
-
-                    let dds = image_dds::dds_from_image(
-                        &rgba_image,
-                        dds_format,
-                        image_dds::Quality::Fast,
-                        image_dds::Mipmaps::GeneratedAutomatic,
-                    )?;
-
-                    source_relative_path.set_extension("dds");
-                    let output_path = Path::new(&output_path).join(source_relative_path);
-                    let mut writer = std::io::BufWriter::new(std::fs::File::create(&output_path)?);
-                    dds.write(&mut writer)?;
-                    debug!("{:?} created", output_path);
-
-                    Ok(())
-                })
-        })?;
-    info!("converting ended. total files: {}", files_size);
-
-    Ok(files_size)
+    reports
 }
 
-fn images_to_images_sequential(
+fn images_to_images_parallel(
     files: Vec<String>,
     source_dir: String,
     output_path: String,
-    output_format: ImageFormatEnum,
-) -> anyhow::Result<usize> {
+    options: &ConvertOptions,
+    renamed: &HashMap<String, PathBuf>,
+    progress: &ProgressTracker,
+) -> Vec<FileReport> {
     info!("converting start");
 
-    let files_size = files.len();
+    let output_format_string: &str = options.output_format.into();
+    let stopped = AtomicBool::new(false);
+
+    let reports: Vec<FileReport> = files
+        .par_chunks(5)
+        .flat_map_iter(|chunk| {
+            chunk.iter().map(|path_string| {
+                if let Some(report) =
+                    skip_unstarted_file(path_string, stopped.load(Ordering::Relaxed), progress)
+                {
+                    return report;
+                }
+                let report = convert_file(
+                    path_string,
+                    output_for(
+                        path_string,
+                        &source_dir,
+                        &output_path,
+                        output_format_string,
+                        renamed,
+                    ),
+                    options.overwrite,
+                    progress,
+                    |output| image_to_image(path_string, output, options),
+                );
+                if stops_conversion(&report, options) {
+                    stopped.store(true, Ordering::Relaxed);
+                }
+                report
+            })
+        })
+        .collect();
+    info!("converting ended. total files: {}", reports.len());
+
+    reports
+}
+
+/// Report files that are not started because the conversion stopped or was cancelled.
+fn skip_unstarted_file(
+    path_string: &str,
+    stopped: bool,
+    progress: &ProgressTracker,
+) -> Option<FileReport> {
+    if progress.is_cancelled() {
+        Some(skip_file(path_string, SkipReason::Cancelled, progress))
+    } else if stopped {
+        Some(skip_file(path_string, SkipReason::NotProcessed, progress))
+    } else {
+        None
+    }
+}
 
-    for path_string in files {
-        let cloned_path = path_string.clone();
-        // in order to support processing directory recursive, get diff between current path and source path
-        let mut source_relative_path =
-            diff_paths(Path::new(&cloned_path), Path::new(&source_dir.clone())).ok_or_else(
-                || anyhow::anyhow!("Failed to compute relative path for {}", cloned_path),
-            )?;
+/// whether the files after `report` should be left alone
+fn stops_conversion(report: &FileReport, options: &ConvertOptions) -> bool {
+    report.status == FileStatus::Failed && options.error_policy == ErrorPolicy::FailFast
+}
 
-        let output_format_string: &str = output_format.into();
-        source_relative_path.set_extension(output_format_string);
-        let output_path = Path::new(&output_path).join(source_relative_path);
+/// Why `path_string` is not converted at all, checked before the conversion starts.
+pub(crate) fn skip_reason(
+    path_string: &str,
+    options: &ConvertOptions,
+    cache: Option<&mut BuildCache>,
+) -> Option<SkipReason> {
+    if has_format_extension(Path::new(path_string), options.output_format) {
+        // to prevent processing same image format, filter out from files
+        Some(SkipReason::SameFormat)
+    } else if !Path::new(path_string).exists() {
+        Some(SkipReason::NotFound)
+    } else if cache.is_some_and(|cache| cache.is_unchanged(path_string, options)) {
+        Some(SkipReason::Unchanged)
+    } else {
+        None
+    }
+}
 
-        if cloned_path.ends_with(".dds") {
-            let mut reader = std::fs::File::open(path_string)?;
-            let dds = ddsfile::Dds::read(&mut reader)?;
+fn skip_file(path_string: &str, reason: SkipReason, progress: &ProgressTracker) -> FileReport {
+    let report = FileReport::skipped(path_string, reason);
+    progress.file_finished(&report);
+    report
+}
 
-            let image = image_dds::image_from_dds(&dds, 0)?;
+fn fail_file(path_string: &str, error: String, progress: &ProgressTracker) -> FileReport {
+    let report = FileReport {
+        status: FileStatus::Failed,
+        error: Some(error),
+        ..FileReport::skipped(path_string, SkipReason::NotProcessed)
+    };
+    progress.file_finished(&report);
+    report
+}
 
-            image.save(&output_path)?;
-        } else {
-            let image = image_dds::image::open(Path::new(&path_string))?;
-            image.save(&output_path)?;
+/// How the inputs of `files` that would write the same output are resolved.
+/// Inputs whose output path can't be computed fail later with their own error.
+pub(crate) fn collision_resolutions(
+    files: &[String],
+    source_dir: &str,
+    output_path: &str,
+    options: &ConvertOptions,
+) -> HashMap<String, Resolution> {
+    let extension: &str = options.output_format.into();
+    let outputs: Vec<(String, PathBuf)> = files
+        .iter()
+        .filter_map(|path| {
+            let output = get_output_path(path, source_dir, output_path, extension).ok()?;
+            Some((path.clone(), output))
+        })
+        .collect();
+    resolve_collisions(&outputs, options)
+}
+
+/// Output of `path_string`: the path given by [`resolve_collisions`] when it was renamed,
+/// the same path relative to `output_path` as it has relative to `source_dir` otherwise.
+fn output_for(
+    path_string: &str,
+    source_dir: &str,
+    output_path: &str,
+    extension: &str,
+    renamed: &HashMap<String, PathBuf>,
+) -> anyhow::Result<PathBuf> {
+    match renamed.get(path_string) {
+        Some(output) => Ok(output.clone()),
+        None => get_output_path(path_string, source_dir, output_path, extension),
+    }
+}
+
+/// Run `write_output` for `path_string` and record how it went.
+fn convert_file(
+    path_string: &str,
+    output: anyhow::Result<PathBuf>,
+    overwrite: OverwritePolicy,
+    progress: &ProgressTracker,
+    write_output: impl FnOnce(&Path) -> anyhow::Result<()>,
+) -> FileReport {
+    progress.file_started(path_string);
+    let start = Instant::now();
+    let input_bytes = std::fs::metadata(path_string).map_or(0, |metadata| metadata.len());
+
+    let target = output.and_then(|output| resolve_output(path_string, output, overwrite));
+    let (output, output_action, result) = match target {
+        Ok(OutputTarget::Skip(output, reason)) => {
+            debug!("{:?} skipped: {}", output, reason);
+            let report = FileReport {
+                output: Some(output),
+                duration: start.elapsed(),
+                ..FileReport::skipped(path_string, reason)
+            };
+            progress.file_finished(&report);
+            return report;
+        }
+        Ok(OutputTarget::Write(output, action)) => {
+            let result =
+                create_output_dir(&output).and_then(|_| write_atomically(&output, write_output));
+            (Some(output), Some(action), result)
         }
-        debug!("{:?} created", output_path);
+        Err(e) => (None, None, Err(e)),
+    };
+
+    let (status, error, output_bytes) = match result {
+        Ok(()) => {
+            debug!(
+                "{:?} {}",
+                output,
+                output_action.unwrap_or(OutputAction::Created)
+            );
+            let output_bytes = output
+                .as_ref()
+                .and_then(|output| std::fs::metadata(output).ok())
+                .map_or(0, |metadata| metadata.len());
+            (FileStatus::Converted, None, output_bytes)
+        }
+        Err(e) => (FileStatus::Failed, Some(e.to_string()), 0),
+    };
+
+    let report = FileReport {
+        input: PathBuf::from(path_string),
+        output,
+        output_action: output_action.filter(|_| status == FileStatus::Converted),
+        status,
+        error,
+        duration: start.elapsed(),
+        input_bytes,
+        output_bytes,
+    };
+    progress.file_finished(&report);
+    report
+}
+
+pub(crate) enum OutputTarget {
+    Write(PathBuf, OutputAction),
+    Skip(PathBuf, SkipReason),
+}
+
+/// Apply `overwrite` when something already exists at `output`.
+pub(crate) fn resolve_output(
+    path_string: &str,
+    output: PathBuf,
+    overwrite: OverwritePolicy,
+) -> anyhow::Result<OutputTarget> {
+    if !output.exists() {
+        return Ok(OutputTarget::Write(output, OutputAction::Created));
     }
-    info!("converting ended. total files: {}", files_size);
 
-    Ok(files_size)
+    let target = match overwrite {
+        OverwritePolicy::Overwrite => OutputTarget::Write(output, OutputAction::Overwritten),
+        OverwritePolicy::SkipExisting => OutputTarget::Skip(output, SkipReason::OutputExists),
+        OverwritePolicy::SkipIfNewer => {
+            let input_modified = std::fs::metadata(path_string)?.modified()?;
+            let output_modified = std::fs::metadata(&output)?.modified()?;
+            if output_modified >= input_modified {
+                OutputTarget::Skip(output, SkipReason::UpToDate)
+            } else {
+                OutputTarget::Write(output, OutputAction::Overwritten)
+            }
+        }
+        OverwritePolicy::Rename => {
+            OutputTarget::Write(numbered_path(&output), OutputAction::Renamed)
+        }
+    };
+    Ok(target)
 }
 
-fn images_to_images_parallel(
-    files: Vec<String>,
-    source_dir: String,
-    output_path: String,
-    output_format: ImageFormatEnum,
-) -> anyhow::Result<usize> {
-    info!("converting start");
+/// First of `name_1.ext`, `name_2.ext`, ... that does not exist yet.
+fn numbered_path(output: &Path) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = output.extension().unwrap_or_default().to_string_lossy();
+
+    (1..)
+        .map(|number| output.with_file_name(format!("{}_{}.{}", stem, number, extension)))
+        .find(|path| !path.exists())
+        .expect("ran out of numbered file names")
+}
 
-    let files_size = files.len();
+/// in order to support processing directory recursive, get diff between current path and source path
+pub(crate) fn get_output_path(
+    path_string: &str,
+    source_dir: &str,
+    output_path: &str,
+    extension: &str,
+) -> anyhow::Result<PathBuf> {
+    let mut source_relative_path = diff_paths(Path::new(path_string), Path::new(source_dir))
+        .ok_or_else(|| anyhow::anyhow!("Failed to compute relative path for {}", path_string))?;
+    // an absolute path or `..` would write outside of `output_path`
+    if source_relative_path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!(
+            "{} is outside the source folder {}",
+            path_string,
+            source_dir
+        );
+    }
+    source_relative_path.set_extension(extension);
 
-    files
-        .par_chunks(5)
-        .try_for_each(|chunk| -> anyhow::Result<()> {
-            chunk
-                .iter()
-                .try_for_each(|path_string| -> anyhow::Result<()> {
-                    let cloned_path = path_string.clone();
-                    // in order to support processing directory recursive, get diff between current path and source path
-                    let mut source_relative_path =
-                        diff_paths(Path::new(&cloned_path), Path::new(&source_dir.clone()))
-                            .ok_or_else(|| {
-                                anyhow::anyhow!(
-                                    "Failed to compute relative path for {}",
-                                    cloned_path
-                                )
-                            })?;
-
-                    let output_format_string: &str = output_format.into();
-                    source_relative_path.set_extension(output_format_string);
-                    let output_path = Path::new(&output_path).join(source_relative_path);
-
-                    if cloned_path.ends_with(".dds") {
-                        let mut reader = std::fs::File::open(path_string)?;
-                        let dds = ddsfile::Dds::read(&mut reader)?;
-
-                        let image = image_dds::image_from_dds(&dds, 0)?;
-
-                        image.save(&output_path)?;
-                    } else {
-                        let image = image_dds::image::open(Path::new(path_string))?;
-                        image.save(&output_path)?;
-                    }
-
-                    debug!("{:?} created", output_path);
-
-                    Ok(())
-                })
-        })?;
-    info!("converting ended. total files: {}", files_size);
-
-    Ok(files_size)
+    Ok(Path::new(output_path).join(source_relative_path))
+}
+
+/// mirror the source tree, the sub folder of `output` may not exist yet in the destination
+fn create_output_dir(output: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    Ok(())
+}
+
+/// Encodes the image at `path_string` with its DDS settings and writes it as DDS or KTX2.
+fn image_to_texture(
+    path_string: &str,
+    output_path: &Path,
+    options: &ConvertOptions,
+) -> anyhow::Result<()> {
+    let settings = options.dds_settings(Path::new(path_string))?;
+    debug!("{} uses {:?}", path_string, settings);
+
+    let image = open_image(Path::new(path_string))?;
+    let mipmaps = settings.mipmaps.to_mipmaps(image.width(), image.height());
+
+    // HDR sources and float formats go through f32 so values above 1 are not clamped
+    let surface = if is_float_image(&image) || is_float_format(settings.format) {
+        SurfaceRgba32Float::from_image(&image.to_rgba32f()).encode(
+            settings.format,
+            settings.quality,
+            mipmaps,
+        )?
+    } else {
+        SurfaceRgba8::from_image(&image.to_rgba8()).encode(
+            settings.format,
+            settings.quality,
+            mipmaps,
+        )?
+    };
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(output_path)?);
+    match options.output_format {
+        ImageFormatEnum::KTX2 => write_ktx2(&surface, &mut writer)?,
+        _ => surface.to_dds()?.write(&mut writer)?,
+    }
+    // dropping the writer would ignore a failed flush and the partial file would be kept
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn image_to_image(
+    path_string: &str,
+    output_path: &Path,
+    options: &ConvertOptions,
+) -> anyhow::Result<()> {
+    let image = open_image(Path::new(path_string))?;
+    match options.output_format {
+        ImageFormatEnum::WEBP => save_webp(&image, output_path, options.webp)?,
+        ImageFormatEnum::ICO => save_ico(&image, output_path)?,
+        ImageFormatEnum::ICNS => save_icns(&image, output_path)?,
+        format => with_supported_color(image, format).save(output_path)?,
+    }
+
+    Ok(())
+}
+
+/// `image` in a color type the encoder of `format` writes. EXR keeps floats, 16 bits per
+/// channel are kept for PNG and TIFF and the other formats only have 8.
+fn with_supported_color(image: DynamicImage, format: ImageFormatEnum) -> DynamicImage {
+    use image_dds::image::ColorType;
+
+    match (format, image.color()) {
+        (ImageFormatEnum::EXR, ColorType::Rgb32F | ColorType::Rgba32F) => image,
+        (ImageFormatEnum::EXR, color) if color.has_alpha() => {
+            DynamicImage::ImageRgba32F(image.to_rgba32f())
+        }
+        (ImageFormatEnum::EXR, _) => DynamicImage::ImageRgb32F(image.to_rgb32f()),
+        (ImageFormatEnum::PNG | ImageFormatEnum::TIFF | ImageFormatEnum::TIF, color) => {
+            // TIFF has no gray with alpha, neither format has floats
+            match color {
+                ColorType::La8 if format != ImageFormatEnum::PNG => {
+                    DynamicImage::ImageRgba8(image.to_rgba8())
+                }
+                ColorType::La16 if format != ImageFormatEnum::PNG => {
+                    DynamicImage::ImageRgba16(image.to_rgba16())
+                }
+                ColorType::Rgb32F => DynamicImage::ImageRgb16(image.to_rgb16()),
+                ColorType::Rgba32F => DynamicImage::ImageRgba16(image.to_rgba16()),
+                _ => image,
+            }
+        }
+        (ImageFormatEnum::GIF, color) if color.has_alpha() => {
+            DynamicImage::ImageRgba8(image.to_rgba8())
+        }
+        (ImageFormatEnum::GIF, _) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        (_, ColorType::L16) => DynamicImage::ImageLuma8(image.to_luma8()),
+        (_, ColorType::La16) => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+        (_, ColorType::Rgb16 | ColorType::Rgb32F) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        (_, ColorType::Rgba16 | ColorType::Rgba32F) => DynamicImage::ImageRgba8(image.to_rgba8()),
+        _ => image,
+    }
+}
+
+/// The `image` crate only writes lossless WebP, libwebp does both.
+fn save_webp(
+    image: &DynamicImage,
+    output_path: &Path,
+    compression: WebpCompression,
+) -> anyhow::Result<()> {
+    let (pixels, layout) = if image.color().has_alpha() {
+        (image.to_rgba8().into_raw(), webp::PixelLayout::Rgba)
+    } else {
+        (image.to_rgb8().into_raw(), webp::PixelLayout::Rgb)
+    };
+    let encoder = webp::Encoder::new(&pixels, layout, image.width(), image.height());
+    let encoded = match compression {
+        WebpCompression::Lossless => encoder.encode_simple(true, 100.0),
+        WebpCompression::Lossy(quality) => encoder.encode_simple(false, f32::from(quality)),
+    }
+    .map_err(|e| anyhow::anyhow!("failed to encode WebP: {:?}", e))?;
+
+    std::fs::write(output_path, &*encoded)?;
+    Ok(())
+}
+
+/// `true` for the DDS formats storing floats, which keep values outside of 0 to 1.
+pub(crate) fn is_float_format(format: image_dds::ImageFormat) -> bool {
+    matches!(
+        format,
+        image_dds::ImageFormat::BC6hRgbUfloat
+            | image_dds::ImageFormat::BC6hRgbSfloat
+            | image_dds::ImageFormat::Rgba16Float
+            | image_dds::ImageFormat::Rgba32Float
+    )
+}
+
+fn is_float_image(image: &DynamicImage) -> bool {
+    matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    )
+}
+
+/// The `image` decoder of Radiance files tone maps them to 8 bits, this keeps the floats.
+fn open_radiance(file: std::fs::File) -> anyhow::Result<DynamicImage> {
+    let decoder = HdrDecoder::new(std::io::BufReader::new(file))?;
+    let metadata = decoder.metadata();
+    let pixels = decoder.read_image_hdr()?;
+    let image = Rgb32FImage::from_raw(
+        metadata.width,
+        metadata.height,
+        pixels.into_iter().flat_map(|pixel| pixel.0).collect(),
+    )
+    .context("Radiance image has fewer pixels than its size")?;
+    Ok(DynamicImage::ImageRgb32F(image))
+}
+
+/// Decode `path` by what its content starts with, so a PNG saved as `.jpg` is still read.
+/// Formats without a signature, like TGA, are picked by the extension.
+/// DDS files with a float format and HDR/EXR images are decoded to 32 bit floats.
+fn open_image(path: &Path) -> anyhow::Result<DynamicImage> {
+    let mut file = std::fs::File::open(path)?;
+    let mut magic = [0; 4];
+    let has_magic = file.read_exact(&mut magic).is_ok();
+    if has_magic && &magic == DDS_MAGIC {
+        file.rewind()?;
+        let dds = ddsfile::Dds::read(&mut file)?;
+        let image = match image_dds::dds_image_format(&dds) {
+            Ok(format) if is_float_format(format) => {
+                DynamicImage::ImageRgba32F(image_dds::imagef32_from_dds(&dds, 0)?)
+            }
+            _ => DynamicImage::ImageRgba8(image_dds::image_from_dds(&dds, 0)?),
+        };
+        return Ok(image);
+    }
+    if has_magic && magic.starts_with(RADIANCE_MAGIC) {
+        file.rewind()?;
+        return open_radiance(file);
+    }
+
+    let image = image_dds::image::io::Reader::open(path)?
+        .with_guessed_format()?
+        .decode()?;
+    Ok(image)
 }
 
 #[cfg(test)]
@@ -237,6 +799,8 @@ mod tests {
     use std::fs;
 
     use super::*;
+    use crate::format::{CacheMode, CollisionPolicy, MipmapPolicy};
+    use image_dds::image::{ColorType, GenericImageView};
 
     #[test]
     fn test_images_to_dds() {
@@ -246,11 +810,24 @@ mod tests {
         ];
         let source_dir = "./test_images".to_string();
         let output_path = "./test_images".to_string();
-        let dds_format = image_dds::ImageFormat::BC1RgbaUnorm;
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            dds_format: image_dds::ImageFormat::BC1RgbaUnorm,
+            ..Default::default()
+        };
 
-        let convert_result = images_to_dds_sequential(files, source_dir, output_path, dds_format);
+        let convert_result = images_to_textures_sequential(
+            files,
+            source_dir,
+            output_path,
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
 
-        assert!(convert_result.is_ok());
+        assert!(convert_result
+            .iter()
+            .all(|file| file.status == FileStatus::Converted));
 
         assert!(Path::new("./test_images/o-a_base.dds").exists());
         assert!(Path::new("./test_images/sub/o-a_base.dds").exists());
@@ -259,6 +836,356 @@ mod tests {
         fs::remove_file("./test_images/sub/o-a_base.dds").unwrap();
     }
 
+    #[test]
+    fn test_images_to_dds_quality_and_mipmaps() {
+        let output_path = "./test_images/mipmaps_output".to_string();
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            dds_format: image_dds::ImageFormat::BC7RgbaUnorm,
+            dds_quality: image_dds::Quality::Normal,
+            mipmaps: MipmapPolicy::Fixed(3),
+            ..Default::default()
+        };
+
+        let convert_result = images_to_textures_sequential(
+            vec!["./test_images/o-a_base.png".to_string()],
+            "./test_images".to_string(),
+            output_path.clone(),
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
+        assert_eq!(convert_result[0].status, FileStatus::Converted);
+
+        let mut reader = fs::File::open("./test_images/mipmaps_output/o-a_base.dds").unwrap();
+        let dds = ddsfile::Dds::read(&mut reader).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 3);
+
+        fs::remove_dir_all(output_path).unwrap();
+    }
+
+    #[test]
+    fn test_overwrite_policy() {
+        let output_path = "./test_images/overwrite_output";
+        let convert_with = |overwrite| {
+            let options = ConvertOptions {
+                output_format: ImageFormatEnum::TGA,
+                overwrite,
+                ..Default::default()
+            };
+            images_to_images_sequential(
+                vec!["./test_images/o-a_base.png".to_string()],
+                "./test_images".to_string(),
+                output_path.to_string(),
+                &options,
+                &HashMap::new(),
+                &ProgressTracker::new(None, CancellationToken::new(), 0),
+            )
+            .remove(0)
+        };
+
+        let created = convert_with(OverwritePolicy::SkipExisting);
+        assert_eq!(created.status, FileStatus::Converted);
+        assert_eq!(created.output_action, Some(OutputAction::Created));
+
+        let skipped = convert_with(OverwritePolicy::SkipExisting);
+        assert_eq!(
+            skipped.status,
+            FileStatus::Skipped(SkipReason::OutputExists)
+        );
+        let up_to_date = convert_with(OverwritePolicy::SkipIfNewer);
+        assert_eq!(up_to_date.status, FileStatus::Skipped(SkipReason::UpToDate));
+
+        let renamed = convert_with(OverwritePolicy::Rename);
+        assert_eq!(renamed.output_action, Some(OutputAction::Renamed));
+        assert_eq!(
+            renamed.output,
+            Some(Path::new(output_path).join("o-a_base_1.tga"))
+        );
+        assert!(renamed.output.unwrap().exists());
+
+        let overwritten = convert_with(OverwritePolicy::Overwrite);
+        assert_eq!(overwritten.output_action, Some(OutputAction::Overwritten));
+
+        fs::remove_dir_all(output_path).unwrap();
+    }
+
+    #[test]
+    fn test_upper_case_and_misnamed_images() {
+        let source_dir = "./test_images/case_source";
+        fs::create_dir_all(source_dir).unwrap();
+        // a PNG with the extension of another format
+        for file in ["Upper.PNG", "misnamed.jpg"] {
+            fs::copy(
+                "./test_images/o-a_base.png",
+                Path::new(source_dir).join(file),
+            )
+            .unwrap();
+        }
+        fs::write(Path::new(source_dir).join("Lower.jpeg"), "").unwrap();
+
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            ..Default::default()
+        };
+        assert_eq!(
+            skip_reason("./test_images/case_source/A.TGA", &options, None),
+            Some(SkipReason::SameFormat)
+        );
+        let jpeg = ConvertOptions {
+            output_format: ImageFormatEnum::JPEG,
+            ..Default::default()
+        };
+        assert_eq!(
+            skip_reason("./test_images/case_source/Lower.jpeg", &jpeg, None),
+            Some(SkipReason::SameFormat)
+        );
+
+        let mut files = crate::get_image_files(source_dir);
+        files.retain(|file| !file.ends_with("Lower.jpeg"));
+        assert_eq!(files.len(), 2);
+        let report = convert(
+            files,
+            source_dir.to_string(),
+            source_dir.to_string(),
+            &options,
+        );
+        let converted = [
+            Path::new(source_dir).join("Upper.tga"),
+            Path::new(source_dir).join("misnamed.tga"),
+        ]
+        .map(|output| output.exists());
+        fs::remove_dir_all(source_dir).unwrap();
+
+        assert_eq!(report.converted_count(), 2, "{:?}", report.files);
+        assert_eq!(converted, [true, true]);
+    }
+
+    /// Converts `path` alone with `options`, checks it was converted and returns its output.
+    fn convert_one(
+        path: &str,
+        source_dir: &str,
+        output_dir: &str,
+        options: ConvertOptions,
+    ) -> PathBuf {
+        let options = ConvertOptions {
+            use_sequential_convert: true,
+            ..options
+        };
+        let report = convert(
+            vec![path.to_string()],
+            source_dir.to_string(),
+            output_dir.to_string(),
+            &options,
+        );
+        assert_eq!(report.converted_count(), 1, "{:?}", report.files);
+        report.files[0].output.clone().unwrap()
+    }
+
+    #[test]
+    fn test_webp_output_and_input() {
+        let output_dir = "./test_images/webp_output";
+        let png = "./test_images/o-a_base.png";
+        let webp_with = |webp| ConvertOptions {
+            output_format: ImageFormatEnum::WEBP,
+            webp,
+            ..Default::default()
+        };
+
+        let webp = convert_one(
+            png,
+            "./test_images",
+            output_dir,
+            webp_with(WebpCompression::Lossless),
+        );
+        let lossless_bytes = fs::metadata(&webp).unwrap().len();
+        let decoded = image_dds::image::open(&webp).unwrap().to_rgba8();
+        let original = image_dds::image::open(png).unwrap().to_rgba8();
+
+        convert_one(
+            png,
+            "./test_images",
+            output_dir,
+            webp_with(WebpCompression::Lossy(50)),
+        );
+        let lossy_bytes = fs::metadata(&webp).unwrap().len();
+
+        // WebP as the input
+        let back = convert_one(
+            &webp.to_string_lossy(),
+            output_dir,
+            output_dir,
+            ConvertOptions {
+                output_format: ImageFormatEnum::PNG,
+                ..Default::default()
+            },
+        );
+        let png_exists = back.exists();
+        fs::remove_dir_all(output_dir).unwrap();
+
+        assert_eq!(decoded, original);
+        assert!(lossy_bytes < lossless_bytes);
+        assert!(png_exists);
+    }
+
+    #[test]
+    fn test_bmp_tiff_gif_bit_depth() {
+        let source_dir = "./test_images/bit_depth_source";
+        let output_dir = "./test_images/bit_depth_source/out";
+        fs::create_dir_all(source_dir).unwrap();
+        let tiff = Path::new(source_dir).join("deep.tiff");
+        let original = image_dds::image::open("./test_images/o-a_base.png")
+            .unwrap()
+            .to_rgba16();
+        DynamicImage::ImageRgba16(original.clone())
+            .save(&tiff)
+            .unwrap();
+
+        let [png, bmp, gif, jpeg] = [
+            ImageFormatEnum::PNG,
+            ImageFormatEnum::BMP,
+            ImageFormatEnum::GIF,
+            ImageFormatEnum::JPEG,
+        ]
+        .map(|output_format| {
+            let options = ConvertOptions {
+                output_format,
+                ..Default::default()
+            };
+            let output = convert_one(&tiff.to_string_lossy(), source_dir, output_dir, options);
+            image_dds::image::open(output).unwrap()
+        });
+        fs::remove_dir_all(source_dir).unwrap();
+
+        assert_eq!(png.color(), ColorType::Rgba16);
+        assert_eq!(png.to_rgba16(), original);
+        assert_eq!(bmp.color(), ColorType::Rgba8);
+        assert_eq!(
+            bmp.to_rgba8(),
+            DynamicImage::ImageRgba16(original).to_rgba8()
+        );
+        assert_eq!(gif.dimensions(), png.dimensions());
+        assert_eq!(jpeg.color(), ColorType::Rgb8);
+    }
+
+    #[test]
+    fn test_hdr_to_float_dds() {
+        use image_dds::image::codecs::hdr::HdrEncoder;
+        use image_dds::image::{Rgb, Rgba32FImage};
+
+        let source_dir = "./test_images/hdr_source";
+        let output_dir = "./test_images/hdr_source/out";
+        fs::create_dir_all(source_dir).unwrap();
+        let bright = Rgba32FImage::from_pixel(8, 8, [4.0, 2.0, 0.5, 1.0].into());
+        DynamicImage::ImageRgba32F(bright.clone())
+            .save(Path::new(source_dir).join("bright.exr"))
+            .unwrap();
+        HdrEncoder::new(fs::File::create(Path::new(source_dir).join("sky.hdr")).unwrap())
+            .encode(&[Rgb([4.0, 2.0, 0.5]); 64], 8, 8)
+            .unwrap();
+
+        let options = |output_format, dds_format| ConvertOptions {
+            output_format,
+            dds_format,
+            mipmaps: MipmapPolicy::Disabled,
+            cache: CacheMode::Off,
+            ..Default::default()
+        };
+        let read_dds = |path: &Path| {
+            let dds = ddsfile::Dds::read(fs::File::open(path).unwrap()).unwrap();
+            image_dds::imagef32_from_dds(&dds, 0).unwrap()
+        };
+
+        let exr = format!("{}/bright.exr", source_dir);
+        let hdr = format!("{}/sky.hdr", source_dir);
+        let [rgba32, rgba16] = [
+            image_dds::ImageFormat::Rgba32Float,
+            image_dds::ImageFormat::Rgba16Float,
+        ]
+        .map(|dds_format| {
+            let options = options(ImageFormatEnum::DDS, dds_format);
+            read_dds(&convert_one(&exr, source_dir, output_dir, options))
+        });
+        let bc6h_path = convert_one(
+            &hdr,
+            source_dir,
+            output_dir,
+            options(ImageFormatEnum::DDS, image_dds::ImageFormat::BC6hRgbUfloat),
+        );
+        let bc6h = read_dds(&bc6h_path);
+        // a float DDS as the input keeps its range too
+        let exr_back = convert_one(
+            &bc6h_path.to_string_lossy(),
+            output_dir,
+            output_dir,
+            options(ImageFormatEnum::EXR, image_dds::ImageFormat::BC1RgbaUnorm),
+        );
+        let exr_back = image_dds::image::open(exr_back).unwrap().to_rgba32f();
+        fs::remove_dir_all(source_dir).unwrap();
+
+        assert_eq!(rgba32, bright);
+        assert_eq!(rgba16, bright);
+        let red = bc6h.get_pixel(0, 0)[0];
+        assert!((red - 4.0).abs() < 0.1, "{}", red);
+        assert!((exr_back.get_pixel(0, 0)[0] - red).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_incremental_conversion() {
+        let output_path = "./test_images/cache_output";
+        let convert_with = |output_format, cache| {
+            let options = ConvertOptions {
+                output_format,
+                cache,
+                ..Default::default()
+            };
+            convert(
+                vec!["./test_images/o-a_base.png".to_string()],
+                "./test_images".to_string(),
+                output_path.to_string(),
+                &options,
+            )
+            .files
+            .remove(0)
+            .status
+        };
+        let unchanged = FileStatus::Skipped(SkipReason::Unchanged);
+
+        assert_eq!(
+            convert_with(ImageFormatEnum::TGA, CacheMode::Incremental),
+            FileStatus::Converted
+        );
+        assert!(Path::new(output_path)
+            .join(crate::cache::CACHE_FILE_NAME)
+            .exists());
+        assert_eq!(
+            convert_with(ImageFormatEnum::TGA, CacheMode::Incremental),
+            unchanged
+        );
+
+        // a deleted output is converted again
+        fs::remove_file("./test_images/cache_output/o-a_base.tga").unwrap();
+        assert_eq!(
+            convert_with(ImageFormatEnum::TGA, CacheMode::Incremental),
+            FileStatus::Converted
+        );
+
+        assert_eq!(
+            convert_with(ImageFormatEnum::TGA, CacheMode::Rebuild),
+            FileStatus::Converted
+        );
+        assert_eq!(
+            convert_with(ImageFormatEnum::JPG, CacheMode::Incremental),
+            FileStatus::Converted
+        );
+        assert_eq!(
+            convert_with(ImageFormatEnum::JPG, CacheMode::Incremental),
+            unchanged
+        );
+
+        fs::remove_dir_all(output_path).unwrap();
+    }
+
     #[test]
     fn test_dds_to_images() {
         let files = vec![
@@ -267,12 +1194,23 @@ mod tests {
         ];
         let source_dir = "./test_images".to_string();
         let output_path = "./test_images".to_string();
-        let output_format = ImageFormatEnum::PNG;
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::PNG,
+            ..Default::default()
+        };
 
-        let convert_result =
-            images_to_images_sequential(files, source_dir, output_path, output_format);
+        let convert_result = images_to_images_sequential(
+            files,
+            source_dir,
+            output_path,
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
 
-        assert!(convert_result.is_ok());
+        assert!(convert_result
+            .iter()
+            .all(|file| file.status == FileStatus::Converted));
 
         assert!(Path::new("./test_images/o-a_base2.png").exists());
         assert!(Path::new("./test_images/sub/o-a_base2.png").exists());
@@ -289,12 +1227,23 @@ mod tests {
         ];
         let source_dir = "./test_images".to_string();
         let output_path = "./test_images".to_string();
-        let output_format = ImageFormatEnum::TGA;
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            ..Default::default()
+        };
 
-        let convert_result =
-            images_to_images_sequential(files, source_dir, output_path, output_format);
+        let convert_result = images_to_images_sequential(
+            files,
+            source_dir,
+            output_path,
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
 
-        assert!(convert_result.is_ok());
+        assert!(convert_result
+            .iter()
+            .all(|file| file.status == FileStatus::Converted));
 
         assert!(Path::new("./test_images/o-a_base.tga").exists());
         assert!(Path::new("./test_images/sub/o-a_base.tga").exists());
@@ -310,16 +1259,254 @@ mod tests {
         let output_path = "./test_images".to_string();
         let output_format = ImageFormatEnum::TGA;
 
+        let options = ConvertOptions {
+            output_format,
+            dds_format: image_dds::ImageFormat::BC1RgbaUnormSrgb,
+            use_sequential_convert: true,
+            ..Default::default()
+        };
+
+        let convert_result = convert(files, source_dir, output_path, &options);
+
+        assert_eq!(convert_result.converted_count(), 0);
+        assert_eq!(
+            convert_result.files[0].status,
+            FileStatus::Skipped(SkipReason::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_report_failed_file() {
+        fs::write("./test_images/report_corrupt.png", b"not a png").unwrap();
+        let files = vec!["./test_images/report_corrupt.png".to_string()];
+        let source_dir = "./test_images".to_string();
+        let output_path = "./test_images".to_string();
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            use_sequential_convert: true,
+            ..Default::default()
+        };
+
+        let convert_result = convert(files, source_dir, output_path, &options);
+        fs::remove_file("./test_images/report_corrupt.png").unwrap();
+
+        assert!(!convert_result.is_success());
+        let file = &convert_result.files[0];
+        assert_eq!(file.status, FileStatus::Failed);
+        assert!(file.error.is_some());
+        assert_eq!(file.input_bytes, 9);
+        assert!(!Path::new("./test_images/report_corrupt.tga").exists());
+    }
+
+    #[test]
+    fn test_report_keeps_input_order() {
+        let files = vec![
+            "./test_images/o-a_base.png".to_string(),
+            "./test_images/report_order_missing.png".to_string(),
+            "./test_images/sub/o-a_base.png".to_string(),
+        ];
+        let output_path = "./test_images/report_order_output";
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            ..Default::default()
+        };
+
         let convert_result = convert(
+            files.clone(),
+            "./test_images".to_string(),
+            output_path.to_string(),
+            &options,
+        );
+        fs::remove_dir_all(output_path).unwrap();
+
+        let inputs: Vec<PathBuf> = convert_result
+            .files
+            .iter()
+            .map(|file| file.input.clone())
+            .collect();
+        assert_eq!(inputs, files.iter().map(PathBuf::from).collect::<Vec<_>>());
+        assert_eq!(
+            convert_result.files[1].status,
+            FileStatus::Skipped(SkipReason::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_fail_fast_skips_remaining_files() {
+        fs::write("./test_images/fail_fast_corrupt.png", b"not a png").unwrap();
+        let files = vec![
+            "./test_images/fail_fast_corrupt.png".to_string(),
+            "./test_images/o-a_base.png".to_string(),
+        ];
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::JPEG,
+            error_policy: ErrorPolicy::FailFast,
+            ..Default::default()
+        };
+
+        let convert_result = images_to_images_sequential(
             files,
-            source_dir,
-            output_path,
-            output_format,
-            image_dds::ImageFormat::BC1RgbaUnormSrgb,
-            true,
+            "./test_images".to_string(),
+            "./test_images".to_string(),
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
+        fs::remove_file("./test_images/fail_fast_corrupt.png").unwrap();
+
+        assert_eq!(convert_result[0].status, FileStatus::Failed);
+        assert_eq!(
+            convert_result[1].status,
+            FileStatus::Skipped(SkipReason::NotProcessed)
+        );
+        assert!(!Path::new("./test_images/o-a_base.JPEG").exists());
+    }
+
+    #[test]
+    fn test_fail_fast_collision_skips_remaining_files() {
+        let source_dir = "./test_images/collision_source";
+        fs::create_dir_all(source_dir).unwrap();
+        for name in ["collide.png", "collide.tga", "other.png"] {
+            fs::copy("./test_images/o-a_base.png", format!("{source_dir}/{name}")).unwrap();
+        }
+        let files = vec![
+            format!("{source_dir}/collide.png"),
+            format!("{source_dir}/other.png"),
+            format!("{source_dir}/collide.tga"),
+        ];
+        let output_path = "./test_images/collision_output";
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            error_policy: ErrorPolicy::FailFast,
+            collision: CollisionPolicy::Error,
+            ..Default::default()
+        };
+
+        let convert_result = convert(
+            files,
+            source_dir.to_string(),
+            output_path.to_string(),
+            &options,
+        );
+        let written = fs::read_dir(output_path).map_or(0, |dir| dir.count());
+        fs::remove_dir_all(source_dir).unwrap();
+        let _ = fs::remove_dir_all(output_path);
+
+        assert_eq!(convert_result.failed_count(), 2);
+        let other = convert_result
+            .files
+            .iter()
+            .find(|file| file.input.ends_with("other.png"))
+            .unwrap();
+        assert_eq!(other.status, FileStatus::Skipped(SkipReason::NotProcessed));
+        assert_eq!(written, 0);
+    }
+
+    #[test]
+    fn test_reject_files_outside_source_dir() {
+        let absolute = fs::canonicalize("./test_images/o-a_base.png").unwrap();
+        let files = vec![
+            "./test_images/o-a_base.png".to_string(),
+            absolute.to_str().unwrap().to_string(),
+        ];
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            error_policy: ErrorPolicy::Continue,
+            ..Default::default()
+        };
+
+        let convert_result = convert(
+            files,
+            "./test_images/sub".to_string(),
+            "./test_images/outside_output".to_string(),
+            &options,
+        );
+
+        assert_eq!(convert_result.failed_count(), 2);
+        for file in &convert_result.files {
+            assert!(file
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("outside the source folder"));
+        }
+        assert!(!Path::new("./test_images/outside_output").exists());
+    }
+
+    #[test]
+    fn test_create_missing_output_dirs() {
+        let files = vec![
+            "./test_images/o-a_base.png".to_string(),
+            "./test_images/sub/o-a_base.png".to_string(),
+        ];
+        let source_dir = "./test_images".to_string();
+        let output_path = "./test_images/mirror_output".to_string();
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            ..Default::default()
+        };
+
+        let convert_result = convert(files, source_dir, output_path, &options);
+
+        assert_eq!(convert_result.converted_count(), 2);
+        assert!(Path::new("./test_images/mirror_output/o-a_base.dds").exists());
+        assert!(Path::new("./test_images/mirror_output/sub/o-a_base.dds").exists());
+
+        fs::remove_dir_all("./test_images/mirror_output").unwrap();
+    }
+
+    #[test]
+    fn test_cancelled_conversion_skips_files() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let files = vec!["./test_images/o-a_base.png".to_string()];
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            ..Default::default()
+        };
+
+        let convert_result = convert_with_progress(
+            files,
+            "./test_images".to_string(),
+            "./test_images/cancel_output".to_string(),
+            &options,
+            tx,
+            cancel,
         );
 
-        assert_eq!(convert_result, 0);
+        assert!(convert_result.cancelled);
+        assert_eq!(
+            convert_result.files[0].status,
+            FileStatus::Skipped(SkipReason::Cancelled)
+        );
+        assert!(!Path::new("./test_images/cancel_output/o-a_base.tga").exists());
+    }
+
+    #[test]
+    fn test_continue_on_error() {
+        fs::write("./test_images/continue_corrupt.png", b"not a png").unwrap();
+        let files = vec![
+            "./test_images/continue_corrupt.png".to_string(),
+            "./test_images/o-a_base.png".to_string(),
+        ];
+        let source_dir = "./test_images".to_string();
+        let output_path = "./test_images/continue_output".to_string();
+        fs::create_dir_all(&output_path).unwrap();
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::JPG,
+            error_policy: ErrorPolicy::Continue,
+            ..Default::default()
+        };
+
+        let convert_result = convert(files, source_dir, output_path, &options);
+        fs::remove_file("./test_images/continue_corrupt.png").unwrap();
+
+        assert_eq!(convert_result.failed_count(), 1);
+        assert_eq!(convert_result.converted_count(), 1);
+        assert!(Path::new("./test_images/continue_output/o-a_base.JPG").exists());
+
+        fs::remove_dir_all("./test_images/continue_output").unwrap();
     }
 
     #[test]
@@ -332,16 +1519,16 @@ mod tests {
         let output_path = "./test_images".to_string();
         let output_format = ImageFormatEnum::PNG;
 
-        let convert_result = convert(
-            files,
-            source_dir,
-            output_path,
+        let options = ConvertOptions {
             output_format,
-            image_dds::ImageFormat::BC1RgbaUnormSrgb,
-            true,
-        );
+            dds_format: image_dds::ImageFormat::BC1RgbaUnormSrgb,
+            use_sequential_convert: true,
+            ..Default::default()
+        };
+
+        let convert_result = convert(files, source_dir, output_path, &options);
 
-        assert_eq!(convert_result, 1);
+        assert_eq!(convert_result.converted_count(), 1);
         assert!(Path::new("./test_images/o-a_base2.png").exists());
 
         fs::remove_file("./test_images/o-a_base2.png").unwrap();
@@ -357,16 +1544,16 @@ mod tests {
         let output_path = "./test_images".to_string();
         let output_format = ImageFormatEnum::PNG;
 
-        let convert_result = convert(
-            files,
-            source_dir,
-            output_path,
+        let options = ConvertOptions {
             output_format,
-            image_dds::ImageFormat::BC1RgbaUnormSrgb,
-            false,
-        );
+            dds_format: image_dds::ImageFormat::BC1RgbaUnormSrgb,
+            use_sequential_convert: false,
+            ..Default::default()
+        };
 
-        assert_eq!(convert_result, 1);
+        let convert_result = convert(files, source_dir, output_path, &options);
+
+        assert_eq!(convert_result.converted_count(), 1);
         assert!(Path::new("./test_images/o-a_base2.png").exists());
 
         fs::remove_file("./test_images/o-a_base2.png").unwrap();
@@ -402,26 +1589,47 @@ mod tests {
         let source_dir_clone = source_dir.clone();
         let output_path = "./test_images".to_string();
         let output_path_clone = output_path.clone();
-        let dds_format = image_dds::ImageFormat::BC1RgbaUnorm;
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            dds_format: image_dds::ImageFormat::BC1RgbaUnorm,
+            ..Default::default()
+        };
 
         let start = Instant::now();
-        let convert_result = images_to_dds_sequential(files, source_dir, output_path, dds_format);
+        let convert_result = images_to_textures_sequential(
+            files,
+            source_dir,
+            output_path,
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
         let duration = start.elapsed();
 
-        assert!(convert_result.is_ok());
+        assert!(convert_result
+            .iter()
+            .all(|file| file.status == FileStatus::Converted));
         println!(
-            "Time elapsed in images_to_dds_sequential() is: {:?}",
+            "Time elapsed in images_to_textures_sequential() is: {:?}",
             duration
         );
 
         let start = Instant::now();
-        let convert_result =
-            images_to_dds_parallel(files_clone, source_dir_clone, output_path_clone, dds_format);
+        let convert_result = images_to_textures_parallel(
+            files_clone,
+            source_dir_clone,
+            output_path_clone,
+            &options,
+            &HashMap::new(),
+            &ProgressTracker::new(None, CancellationToken::new(), 0),
+        );
         let duration = start.elapsed();
 
-        assert!(convert_result.is_ok());
+        assert!(convert_result
+            .iter()
+            .all(|file| file.status == FileStatus::Converted));
         println!(
-            "Time elapsed in images_to_dds_parallel() is: {:?}",
+            "Time elapsed in images_to_textures_parallel() is: {:?}",
             duration
         );
     }
@@ -0,0 +1,441 @@
+use std::io::Write;
+
+use image_dds::{ImageFormat, Surface};
+
+/// Start of every KTX2 file.
+const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+/// Identifier, 9 header fields and the index of the descriptor, key/value and supercompression
+/// data.
+const HEADER_LENGTH: usize = 80;
+/// Offset, length and uncompressed length of one mip level.
+const LEVEL_INDEX_ENTRY_LENGTH: usize = 24;
+
+// Khronos data format descriptor values
+const MODEL_RGBSDA: u8 = 1;
+const MODEL_BC1A: u8 = 128;
+const MODEL_BC2: u8 = 129;
+const MODEL_BC3: u8 = 130;
+const MODEL_BC4: u8 = 131;
+const MODEL_BC5: u8 = 132;
+const MODEL_BC6H: u8 = 133;
+const MODEL_BC7: u8 = 134;
+const PRIMARIES_BT709: u8 = 1;
+const TRANSFER_LINEAR: u8 = 1;
+const TRANSFER_SRGB: u8 = 2;
+const CHANNEL_RED: u8 = 0;
+const CHANNEL_GREEN: u8 = 1;
+const CHANNEL_BLUE: u8 = 2;
+const CHANNEL_ALPHA: u8 = 15;
+// channels of the BC1A, BC2, BC3, BC6H and BC7 color models
+const CHANNEL_BC_COLOR: u8 = 0;
+const CHANNEL_BC_ALPHA: u8 = 1;
+const QUALIFIER_LINEAR: u8 = 0x10;
+const QUALIFIER_SIGNED: u8 = 0x40;
+const QUALIFIER_FLOAT: u8 = 0x80;
+const FLOAT_MINUS_ONE: u32 = 0xBF80_0000;
+const FLOAT_ONE: u32 = 0x3F80_0000;
+
+/// Writes the mip levels of `surface` as a 2D KTX2 texture without supercompression, the
+/// container Vulkan runtimes load directly.
+pub(crate) fn write_ktx2(surface: &Surface<Vec<u8>>, mut writer: impl Write) -> anyhow::Result<()> {
+    if surface.depth != 1 || surface.layers != 1 {
+        anyhow::bail!("only 2D textures are written to KTX2");
+    }
+    let layout = FormatLayout::of(surface.image_format)?;
+
+    let levels: Vec<&[u8]> = (0..surface.mipmaps)
+        .map(|mipmap| {
+            surface.get(0, 0, mipmap).ok_or_else(|| {
+                anyhow::anyhow!("mip level {} is missing from the encoded texture", mipmap)
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let descriptor = data_format_descriptor(&layout);
+    let key_values = key_value_data();
+
+    let descriptor_offset = HEADER_LENGTH + LEVEL_INDEX_ENTRY_LENGTH * levels.len();
+    let key_values_offset = descriptor_offset + descriptor.len();
+    // levels are stored from the smallest to the full size image
+    let mut level_offsets = vec![0; levels.len()];
+    let mut end = key_values_offset + key_values.len();
+    for (mipmap, level) in levels.iter().enumerate().rev() {
+        end = end.next_multiple_of(layout.alignment());
+        level_offsets[mipmap] = end;
+        end += level.len();
+    }
+
+    let mut header = Vec::with_capacity(descriptor_offset);
+    header.extend_from_slice(&IDENTIFIER);
+    for field in [
+        layout.vk_format,
+        layout.type_size,
+        surface.width,
+        surface.height,
+        // 0 for 2D textures and non-array textures
+        0,
+        0,
+        // faces
+        1,
+        surface.mipmaps,
+        // supercompression scheme
+        0,
+        descriptor_offset as u32,
+        descriptor.len() as u32,
+        key_values_offset as u32,
+        key_values.len() as u32,
+    ] {
+        header.extend_from_slice(&field.to_le_bytes());
+    }
+    // no supercompression global data
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    for (offset, level) in level_offsets.iter().zip(&levels) {
+        header.extend_from_slice(&(*offset as u64).to_le_bytes());
+        header.extend_from_slice(&(level.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(level.len() as u64).to_le_bytes());
+    }
+
+    writer.write_all(&header)?;
+    writer.write_all(&descriptor)?;
+    writer.write_all(&key_values)?;
+    let mut written = key_values_offset + key_values.len();
+    for mipmap in (0..levels.len()).rev() {
+        writer.write_all(&vec![0; level_offsets[mipmap] - written])?;
+        writer.write_all(levels[mipmap])?;
+        written = level_offsets[mipmap] + levels[mipmap].len();
+    }
+    Ok(())
+}
+
+/// How the texels of an [`ImageFormat`] are stored.
+struct FormatLayout {
+    vk_format: u32,
+    /// Size of the data type for endianness conversion, 1 for compressed formats.
+    type_size: u32,
+    /// Width and height of a block of texels, 4 for the BC formats.
+    block_size: u8,
+    color_model: u8,
+    srgb: bool,
+    /// Channel id with its qualifiers, bit offset and bit length of every sample.
+    samples: Vec<(u8, u16, u8)>,
+    /// Value range of the samples.
+    lower: u32,
+    upper: u32,
+}
+
+impl FormatLayout {
+    fn of(format: ImageFormat) -> anyhow::Result<Self> {
+        use ImageFormat as F;
+
+        let layout = match format {
+            F::R8Unorm => Self::uncompressed(9, false, vec![(CHANNEL_RED, 0, 8)], 255),
+            F::Rgba8Unorm => {
+                Self::uncompressed(37, false, rgba_samples(false, [0, 8, 16, 24], 8), 255)
+            }
+            F::Rgba8UnormSrgb => {
+                Self::uncompressed(43, true, rgba_samples(true, [0, 8, 16, 24], 8), 255)
+            }
+            F::Bgra8Unorm => {
+                Self::uncompressed(44, false, rgba_samples(false, [16, 8, 0, 24], 8), 255)
+            }
+            F::Bgra8UnormSrgb => {
+                Self::uncompressed(50, true, rgba_samples(true, [16, 8, 0, 24], 8), 255)
+            }
+            // A4R4G4B4 packed in 16 bits, blue in the lowest bits
+            F::Bgra4Unorm => Self {
+                type_size: 2,
+                ..Self::uncompressed(
+                    1_000_340_000,
+                    false,
+                    rgba_samples(false, [8, 4, 0, 12], 4),
+                    15,
+                )
+            },
+            F::Rgba16Float => Self::float(97, 16),
+            F::Rgba32Float => Self::float(109, 32),
+            // BC1 alpha is a punch-through flag stored with the colors
+            F::BC1RgbaUnorm => {
+                Self::compressed(133, MODEL_BC1A, false, vec![(CHANNEL_BC_ALPHA, 0, 64)])
+            }
+            F::BC1RgbaUnormSrgb => {
+                Self::compressed(134, MODEL_BC1A, true, vec![(CHANNEL_BC_ALPHA, 0, 64)])
+            }
+            F::BC2RgbaUnorm => Self::compressed(135, MODEL_BC2, false, alpha_and_color_samples()),
+            F::BC2RgbaUnormSrgb => {
+                Self::compressed(136, MODEL_BC2, true, alpha_and_color_samples())
+            }
+            F::BC3RgbaUnorm => Self::compressed(137, MODEL_BC3, false, alpha_and_color_samples()),
+            F::BC3RgbaUnormSrgb => {
+                Self::compressed(138, MODEL_BC3, true, alpha_and_color_samples())
+            }
+            F::BC4RUnorm => Self::compressed(139, MODEL_BC4, false, vec![(CHANNEL_RED, 0, 64)]),
+            F::BC4RSnorm => {
+                Self::compressed(140, MODEL_BC4, false, vec![(CHANNEL_RED, 0, 64)]).signed()
+            }
+            F::BC5RgUnorm => Self::compressed(141, MODEL_BC5, false, red_green_samples()),
+            F::BC5RgSnorm => Self::compressed(142, MODEL_BC5, false, red_green_samples()).signed(),
+            F::BC6hRgbUfloat => Self::bc6h(143, false),
+            F::BC6hRgbSfloat => Self::bc6h(144, true),
+            F::BC7RgbaUnorm => {
+                Self::compressed(145, MODEL_BC7, false, vec![(CHANNEL_BC_COLOR, 0, 128)])
+            }
+            F::BC7RgbaUnormSrgb => {
+                Self::compressed(146, MODEL_BC7, true, vec![(CHANNEL_BC_COLOR, 0, 128)])
+            }
+            _ => anyhow::bail!("{:?} can not be written to KTX2", format),
+        };
+        Ok(layout)
+    }
+
+    fn uncompressed(vk_format: u32, srgb: bool, samples: Vec<(u8, u16, u8)>, upper: u32) -> Self {
+        Self {
+            vk_format,
+            type_size: 1,
+            block_size: 1,
+            color_model: MODEL_RGBSDA,
+            srgb,
+            samples,
+            lower: 0,
+            upper,
+        }
+    }
+
+    fn compressed(
+        vk_format: u32,
+        color_model: u8,
+        srgb: bool,
+        samples: Vec<(u8, u16, u8)>,
+    ) -> Self {
+        Self {
+            vk_format,
+            type_size: 1,
+            block_size: 4,
+            color_model,
+            srgb,
+            samples,
+            lower: 0,
+            upper: u32::MAX,
+        }
+    }
+
+    /// RGBA with `bits` per channel, floats are described by their range of -1 to 1.
+    fn float(vk_format: u32, bits: u8) -> Self {
+        let offsets = [0, 1, 2, 3].map(|channel| channel * u16::from(bits));
+        Self {
+            type_size: u32::from(bits / 8),
+            lower: FLOAT_MINUS_ONE,
+            upper: FLOAT_ONE,
+            ..Self::uncompressed(vk_format, false, rgba_samples(false, offsets, bits), 0)
+        }
+        .with_qualifiers(QUALIFIER_FLOAT | QUALIFIER_SIGNED)
+    }
+
+    fn bc6h(vk_format: u32, signed: bool) -> Self {
+        let (qualifiers, lower) = if signed {
+            (QUALIFIER_FLOAT | QUALIFIER_SIGNED, FLOAT_MINUS_ONE)
+        } else {
+            (QUALIFIER_FLOAT, 0)
+        };
+        Self {
+            lower,
+            upper: FLOAT_ONE,
+            ..Self::compressed(
+                vk_format,
+                MODEL_BC6H,
+                false,
+                vec![(CHANNEL_BC_COLOR, 0, 128)],
+            )
+        }
+        .with_qualifiers(qualifiers)
+    }
+
+    /// Snorm samples, ranging over every value of a signed 32 bit integer.
+    fn signed(self) -> Self {
+        Self {
+            lower: i32::MIN as u32,
+            upper: i32::MAX as u32,
+            ..self
+        }
+        .with_qualifiers(QUALIFIER_SIGNED)
+    }
+
+    fn with_qualifiers(mut self, qualifiers: u8) -> Self {
+        for (channel, _, _) in &mut self.samples {
+            *channel |= qualifiers;
+        }
+        self
+    }
+
+    fn bytes_per_block(&self) -> u8 {
+        let bits: u32 = self
+            .samples
+            .iter()
+            .map(|&(_, _, bits)| u32::from(bits))
+            .sum();
+        (bits / 8) as u8
+    }
+
+    /// Mip levels start at a multiple of the block size in bytes and of 4.
+    fn alignment(&self) -> usize {
+        match self.bytes_per_block() {
+            1 | 2 => 4,
+            bytes => usize::from(bytes),
+        }
+    }
+}
+
+/// Red, green, blue and alpha samples at `offsets`, ordered by offset. Alpha stays linear in
+/// sRGB formats.
+fn rgba_samples(srgb: bool, offsets: [u16; 4], bits: u8) -> Vec<(u8, u16, u8)> {
+    let alpha = if srgb {
+        CHANNEL_ALPHA | QUALIFIER_LINEAR
+    } else {
+        CHANNEL_ALPHA
+    };
+    let mut samples: Vec<_> = [CHANNEL_RED, CHANNEL_GREEN, CHANNEL_BLUE, alpha]
+        .into_iter()
+        .zip(offsets)
+        .map(|(channel, offset)| (channel, offset, bits))
+        .collect();
+    samples.sort_by_key(|&(_, offset, _)| offset);
+    samples
+}
+
+/// BC2 and BC3 blocks, 64 bits of alpha followed by 64 bits of color.
+fn alpha_and_color_samples() -> Vec<(u8, u16, u8)> {
+    vec![(CHANNEL_BC_ALPHA, 0, 64), (CHANNEL_BC_COLOR, 64, 64)]
+}
+
+/// BC5 blocks, a BC4 block for each channel.
+fn red_green_samples() -> Vec<(u8, u16, u8)> {
+    vec![(CHANNEL_RED, 0, 64), (CHANNEL_GREEN, 64, 64)]
+}
+
+/// The basic data format descriptor block, preceded by the total descriptor size.
+fn data_format_descriptor(layout: &FormatLayout) -> Vec<u8> {
+    let block_length = 24 + 16 * layout.samples.len();
+    let mut descriptor = Vec::with_capacity(4 + block_length);
+    descriptor.extend_from_slice(&(4 + block_length as u32).to_le_bytes());
+    // Khronos vendor and the basic descriptor type
+    descriptor.extend_from_slice(&0u32.to_le_bytes());
+    // version 1.3 of the data format specification
+    descriptor.extend_from_slice(&2u16.to_le_bytes());
+    descriptor.extend_from_slice(&(block_length as u16).to_le_bytes());
+    descriptor.extend_from_slice(&[
+        layout.color_model,
+        PRIMARIES_BT709,
+        if layout.srgb {
+            TRANSFER_SRGB
+        } else {
+            TRANSFER_LINEAR
+        },
+        // straight alpha
+        0,
+    ]);
+    // block dimensions minus one, depth and the fourth dimension are 1
+    let block = layout.block_size - 1;
+    descriptor.extend_from_slice(&[block, block, 0, 0]);
+    // bytes in each of the 8 planes, only the first one is used
+    descriptor.extend_from_slice(&[layout.bytes_per_block(), 0, 0, 0, 0, 0, 0, 0]);
+
+    for &(channel, offset, length) in &layout.samples {
+        descriptor.extend_from_slice(&offset.to_le_bytes());
+        descriptor.extend_from_slice(&[length - 1, channel]);
+        // sample position in the block
+        descriptor.extend_from_slice(&[0; 4]);
+        descriptor.extend_from_slice(&layout.lower.to_le_bytes());
+        descriptor.extend_from_slice(&layout.upper.to_le_bytes());
+    }
+    descriptor
+}
+
+/// Names the program that wrote the file, as recommended for every KTX2 file.
+fn key_value_data() -> Vec<u8> {
+    let mut entry = b"KTXwriter\0".to_vec();
+    entry.extend_from_slice(format!("image_converter v{}\0", env!("CARGO_PKG_VERSION")).as_bytes());
+
+    let mut data = (entry.len() as u32).to_le_bytes().to_vec();
+    data.extend_from_slice(&entry);
+    data.resize(data.len().next_multiple_of(4), 0);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use image_dds::image::RgbaImage;
+    use image_dds::{Mipmaps, Quality, SurfaceRgba8};
+    use ktx2::{ColorModel, Format, Reader, TransferFunction};
+
+    use super::*;
+
+    #[test]
+    fn test_write_ktx2() {
+        let image = RgbaImage::from_fn(16, 8, |x, y| [x as u8 * 16, y as u8 * 32, 128, 255].into());
+        for (format, expected, model, transfer) in [
+            (
+                ImageFormat::BC7RgbaUnormSrgb,
+                Format::BC7_SRGB_BLOCK,
+                ColorModel::BC7,
+                TransferFunction::SRGB,
+            ),
+            (
+                ImageFormat::BC1RgbaUnorm,
+                Format::BC1_RGBA_UNORM_BLOCK,
+                ColorModel::BC1A,
+                TransferFunction::Linear,
+            ),
+            (
+                ImageFormat::BC6hRgbUfloat,
+                Format::BC6H_UFLOAT_BLOCK,
+                ColorModel::BC6H,
+                TransferFunction::Linear,
+            ),
+            (
+                ImageFormat::Rgba8UnormSrgb,
+                Format::R8G8B8A8_SRGB,
+                ColorModel::RGBSDA,
+                TransferFunction::SRGB,
+            ),
+            (
+                ImageFormat::Rgba16Float,
+                Format::R16G16B16A16_SFLOAT,
+                ColorModel::RGBSDA,
+                TransferFunction::Linear,
+            ),
+        ] {
+            let surface = SurfaceRgba8::from_image(&image)
+                .encode(format, Quality::Fast, Mipmaps::GeneratedAutomatic)
+                .unwrap();
+            let mut bytes = Vec::new();
+            write_ktx2(&surface, &mut bytes).unwrap();
+
+            let reader = Reader::new(&bytes).unwrap();
+            let header = reader.header();
+            assert_eq!(header.format, Some(expected));
+            assert_eq!((header.pixel_width, header.pixel_height), (16, 8));
+            assert_eq!(header.level_count, surface.mipmaps);
+            assert!(surface.mipmaps > 1);
+            for (mipmap, level) in reader.levels().enumerate() {
+                assert_eq!(
+                    Some(level),
+                    surface.get(0, 0, mipmap as u32),
+                    "{:?}",
+                    format
+                );
+            }
+
+            let descriptor = reader.data_format_descriptors().next().unwrap();
+            let basic = ktx2::BasicDataFormatDescriptor::parse(descriptor.data).unwrap();
+            assert_eq!(basic.color_model, Some(model));
+            assert_eq!(basic.transfer_function, Some(transfer));
+            let block_bytes: u32 = basic
+                .sample_information()
+                .map(|sample| sample.bit_length)
+                .sum::<u32>()
+                / 8;
+            assert_eq!(basic.bytes_planes[0], block_bytes, "{:?}", format);
+        }
+    }
+}
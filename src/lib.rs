@@ -0,0 +1,59 @@
+//! Conversion engine of the image converter app.
+//!
+//! Converts folders of PNG, DDS, TGA, JPEG, WebP, BMP, TIFF, GIF, HDR and EXR images into a
+//! single output format, keeping the folder structure of the source directory. The engine does
+//! not depend on eframe/egui; add this crate with `default-features = false` to leave out the
+//! GUI.
+//! Images can also be turned into multi-resolution ICO and ICNS icons.
+//!
+//! ```no_run
+//! use image_converter::{convert, get_image_files, ConvertOptions, ImageFormatEnum};
+//!
+//! let files = get_image_files("./textures");
+//! let options = ConvertOptions {
+//!     output_format: ImageFormatEnum::DDS,
+//!     dds_format: image_converter::image_dds::ImageFormat::BC7RgbaUnormSrgb,
+//!     ..Default::default()
+//! };
+//! let report = convert(files, "./textures".to_string(), "./out".to_string(), &options);
+//! for failed in report.failed() {
+//!     eprintln!("{:?}: {:?}", failed.input, failed.error);
+//! }
+//! println!("{}", report);
+//! ```
+
+mod atomic;
+mod cache;
+mod cancel;
+mod collision;
+mod format;
+mod icon;
+mod image_converter;
+mod ktx2;
+mod plan;
+mod preset;
+mod progress;
+mod report;
+mod rules;
+mod scan;
+mod watch;
+
+pub use cancel::CancellationToken;
+pub use format::{
+    CacheMode, CollisionPolicy, ConvertOptions, ErrorPolicy, ImageFormatEnum, MipmapPolicy,
+    OverwritePolicy, WebpCompression,
+};
+pub use icon::ICON_SIZES;
+pub use image_converter::{convert, convert_with_progress};
+pub use plan::{plan, ConversionPlan, PlannedAction, PlannedFile};
+pub use preset::{Preset, Presets};
+pub use progress::{ConvertEvent, Progress};
+pub use report::{ConversionReport, FileReport, FileStatus, OutputAction, SkipReason};
+pub use rules::DdsRule;
+pub use scan::{
+    get_image_files, scan_image_files, ImageScanner, ScanEvent, ScanOptions, IGNORE_FILE_NAME,
+};
+pub use watch::{FolderWatcher, WatchEvent, DEFAULT_DEBOUNCE};
+
+/// Re-exported so callers name DDS formats with the same version the engine uses.
+pub use image_dds;
@@ -3,8 +3,10 @@
 use egui::IconData;
 use std::io::Cursor;
 use std::path::Path;
+use std::process::ExitCode;
 use std::sync::Arc;
 
+use clap::Parser;
 use log::{error, LevelFilter};
 use log4rs::append::console::{ConsoleAppender, Target};
 use log4rs::append::file::FileAppender;
@@ -13,16 +15,14 @@ use log4rs::encode::pattern::PatternEncoder;
 use log4rs::filter::threshold::ThresholdFilter;
 
 mod app;
-mod image_converter;
+mod cli;
 
 fn init_logging() -> anyhow::Result<()> {
     use directories::UserDirs;
 
     let mut appenders = vec![];
 
-    let user_dir = UserDirs::new();
-    if user_dir.is_some() {
-        let user_dir = user_dir.unwrap();
+    if let Some(user_dir) = UserDirs::new() {
         if let Some(document_path) = user_dir.document_dir() {
             let logfile = FileAppender::builder()
                 .encoder(Box::new(PatternEncoder::new(
@@ -40,10 +40,14 @@ fn init_logging() -> anyhow::Result<()> {
     let stderr = ConsoleAppender::builder().target(Target::Stderr).build();
     appenders.push(Appender::builder().build("stderr", Box::new(stderr)));
 
+    // no documents folder (e.g. headless build machines) means there is no logfile appender
+    let appender_names: Vec<String> = appenders
+        .iter()
+        .map(|appender| appender.name().to_string())
+        .collect();
     let config = Config::builder().appenders(appenders).build(
         Root::builder()
-            .appender("logfile")
-            .appender("stderr")
+            .appenders(appender_names)
             .build(LevelFilter::Debug),
     )?;
 
@@ -70,11 +74,49 @@ fn load_embedded_icon() -> Arc<IconData> {
         height,
     })
 }
-fn main() -> eframe::Result<()> {
+
+/// Release builds use the windows subsystem and get no console, so write the output of the
+/// command line to the console of the parent process instead.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // fails when not started from a console, e.g. from the explorer, then nothing is shown
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+fn main() -> ExitCode {
+    // before parsing, so `--help` and argument errors are shown too
+    #[cfg(windows)]
+    if std::env::args_os().len() > 1 {
+        attach_parent_console();
+    }
+    let args = cli::Cli::parse();
+
     init_logging().unwrap_or_else(|e| {
         eprintln!("failed to init logging: {:?}", e);
     });
 
+    match args.command {
+        Some(cli::Command::Convert(convert_args)) => cli::run_convert(convert_args),
+        None => run_gui().map_or_else(
+            |e| {
+                error!("failed to run app: {:?}", e);
+                ExitCode::FAILURE
+            },
+            |_| ExitCode::SUCCESS,
+        ),
+    }
+}
+
+fn run_gui() -> eframe::Result<()> {
     let icon_data = load_embedded_icon();
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_icon(icon_data),
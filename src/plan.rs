@@ -0,0 +1,259 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::cache::BuildCache;
+use crate::collision::Resolution;
+use crate::format::{ConvertOptions, ImageFormatEnum, WebpCompression};
+use crate::image_converter::{
+    collision_resolutions, get_output_path, resolve_output, skip_reason, OutputTarget,
+};
+use crate::report::{OutputAction, SkipReason};
+
+/// What converting a file would do, see [`plan`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedAction {
+    /// The output would be written.
+    Write(OutputAction),
+    Skip(SkipReason),
+    /// Another input would write to the same output, see [`CollisionPolicy::Error`](crate::CollisionPolicy::Error).
+    Collision,
+    /// Converting would fail, e.g. the input is outside the source folder.
+    Error(String),
+}
+
+impl fmt::Display for PlannedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannedAction::Write(OutputAction::Created) => write!(f, "create"),
+            PlannedAction::Write(OutputAction::Overwritten) => write!(f, "overwrite"),
+            PlannedAction::Write(OutputAction::Renamed) => write!(f, "rename"),
+            PlannedAction::Skip(reason) => write!(f, "skip, {}", reason),
+            PlannedAction::Collision => write!(f, "collision"),
+            PlannedAction::Error(e) => write!(f, "error, {}", e),
+        }
+    }
+}
+
+/// One input file of a [`ConversionPlan`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedFile {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    /// Output format, with the DDS format picked for this file.
+    pub format: String,
+    pub action: PlannedAction,
+}
+
+/// What [`convert`](crate::convert) would do with the same arguments, without writing anything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionPlan {
+    pub files: Vec<PlannedFile>,
+}
+
+impl ConversionPlan {
+    /// Inputs that would be written, pass them to [`convert`](crate::convert) to execute the plan.
+    pub fn files_to_convert(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|file| matches!(file.action, PlannedAction::Write(_)))
+            .map(|file| file.input.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    pub fn collision_count(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|file| file.action == PlannedAction::Collision)
+            .count()
+    }
+}
+
+impl fmt::Display for ConversionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            writeln!(
+                f,
+                "{}: {} -> {} ({})",
+                file.action,
+                file.input.display(),
+                file.output
+                    .as_ref()
+                    .map_or("-".into(), |output| output.display().to_string()),
+                file.format
+            )?;
+        }
+        write!(
+            f,
+            "{} to convert, {} collisions",
+            self.files_to_convert().len(),
+            self.collision_count()
+        )
+    }
+}
+
+/// Dry run of [`convert`](crate::convert): computes the output path of every file and whether it
+/// would be written, skipped or collide with another input, without touching the destination.
+pub fn plan(
+    files: &[String],
+    source_dir: &str,
+    output_path: &str,
+    options: &ConvertOptions,
+) -> ConversionPlan {
+    let mut cache = BuildCache::load(source_dir, output_path, options.cache);
+    let extension: &str = options.output_format.into();
+
+    let skip_reasons: Vec<Option<SkipReason>> = files
+        .iter()
+        .map(|path| skip_reason(path, options, cache.as_mut()))
+        .collect();
+    let to_convert: Vec<String> = files
+        .iter()
+        .zip(&skip_reasons)
+        .filter(|(_, reason)| reason.is_none())
+        .map(|(path, _)| path.clone())
+        .collect();
+    let resolutions = collision_resolutions(&to_convert, source_dir, output_path, options);
+
+    let planned = files
+        .iter()
+        .zip(skip_reasons)
+        .map(|(path, reason)| {
+            let format = planned_format(path, options);
+            let (output, action) = match (reason, resolutions.get(path)) {
+                (Some(reason), _) => (None, PlannedAction::Skip(reason)),
+                (None, Some(Resolution::Fail(_))) => (
+                    get_output_path(path, source_dir, output_path, extension).ok(),
+                    PlannedAction::Collision,
+                ),
+                (None, Some(Resolution::Skip)) => (
+                    get_output_path(path, source_dir, output_path, extension).ok(),
+                    PlannedAction::Skip(SkipReason::Collision),
+                ),
+                (None, Some(Resolution::Rename(renamed))) => {
+                    planned_target(path, Ok(renamed.clone()), options)
+                }
+                (None, None) => planned_target(
+                    path,
+                    get_output_path(path, source_dir, output_path, extension),
+                    options,
+                ),
+            };
+            PlannedFile {
+                input: PathBuf::from(path),
+                output,
+                format,
+                action,
+            }
+        })
+        .collect();
+
+    ConversionPlan { files: planned }
+}
+
+/// Applies the overwrite policy to `output`, like converting would.
+fn planned_target(
+    path: &str,
+    output: anyhow::Result<PathBuf>,
+    options: &ConvertOptions,
+) -> (Option<PathBuf>, PlannedAction) {
+    match output.and_then(|output| resolve_output(path, output, options.overwrite)) {
+        Ok(OutputTarget::Write(output, action)) => (Some(output), PlannedAction::Write(action)),
+        Ok(OutputTarget::Skip(output, reason)) => (Some(output), PlannedAction::Skip(reason)),
+        Err(e) => (None, PlannedAction::Error(e.to_string())),
+    }
+}
+
+fn planned_format(path: &str, options: &ConvertOptions) -> String {
+    match options.output_format {
+        format if format.is_gpu_texture() => match options.dds_settings(Path::new(path)) {
+            Ok(settings) => format!("{:?} {:?}", format, settings.format),
+            Err(_) => format!("{:?}", format),
+        },
+        ImageFormatEnum::WEBP => match options.webp {
+            WebpCompression::Lossless => "WEBP lossless".to_string(),
+            WebpCompression::Lossy(quality) => format!("WEBP quality {}", quality),
+        },
+        format => format!("{:?}", format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::format::CollisionPolicy;
+
+    #[test]
+    fn test_plan_does_not_write() {
+        let source_dir = "./test_images/plan_source";
+        fs::create_dir_all(source_dir).unwrap();
+        fs::copy(
+            "./test_images/o-a_base.png",
+            "./test_images/plan_source/o-a_base.png",
+        )
+        .unwrap();
+        fs::copy(
+            "./test_images/o-a_base.png",
+            "./test_images/plan_source/o-a_base.tga",
+        )
+        .unwrap();
+        let files = vec![
+            "./test_images/plan_source/o-a_base.png".to_string(),
+            "./test_images/plan_source/o-a_base.tga".to_string(),
+            "./test_images/o-a_base.png".to_string(),
+            "./test_images/plan_source/missing.png".to_string(),
+        ];
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::DDS,
+            ..Default::default()
+        };
+
+        let plan_with = |collision| {
+            plan(
+                &files,
+                "./test_images",
+                "./test_images/plan_output",
+                &ConvertOptions {
+                    collision,
+                    ..options.clone()
+                },
+            )
+        };
+        let plan = plan_with(CollisionPolicy::Error);
+        let preferred = plan_with(CollisionPolicy::PreferFormat);
+        let suffixed = plan_with(CollisionPolicy::Suffix);
+        fs::remove_dir_all(source_dir).unwrap();
+
+        assert_eq!(plan.files[0].action, PlannedAction::Collision);
+        assert_eq!(plan.files[1].action, PlannedAction::Collision);
+        assert_eq!(
+            plan.files[2].action,
+            PlannedAction::Write(OutputAction::Created)
+        );
+        assert_eq!(
+            plan.files[2].output,
+            Some(PathBuf::from("./test_images/plan_output/o-a_base.dds"))
+        );
+        assert_eq!(plan.files[2].format, "DDS BC1RgbaUnorm");
+        assert_eq!(
+            plan.files[3].action,
+            PlannedAction::Skip(SkipReason::NotFound)
+        );
+        assert_eq!(plan.files_to_convert(), ["./test_images/o-a_base.png"]);
+
+        assert_eq!(
+            preferred.files[1].action,
+            PlannedAction::Skip(SkipReason::Collision)
+        );
+        assert_eq!(preferred.files_to_convert().len(), 2);
+        assert_eq!(
+            suffixed.files[1].output,
+            Some(PathBuf::from(
+                "./test_images/plan_output/plan_source/o-a_base_tga.dds"
+            ))
+        );
+        assert_eq!(suffixed.collision_count(), 0);
+        assert!(!Path::new("./test_images/plan_output").exists());
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::format::{ConvertOptions, ImageFormatEnum, MipmapPolicy, WebpCompression};
+use crate::rules::DdsRule;
+
+/// Named set of conversion settings, shared as a TOML or JSON file.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub output_format: ImageFormatEnum,
+    pub dds_format: image_dds::ImageFormat,
+    pub dds_quality: image_dds::Quality,
+    pub mipmaps: MipmapPolicy,
+    pub dds_rules: Vec<DdsRule>,
+    pub webp: WebpCompression,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self::from_options(String::new(), &ConvertOptions::default())
+    }
+}
+
+impl Preset {
+    pub fn from_options(name: impl Into<String>, options: &ConvertOptions) -> Self {
+        Self {
+            name: name.into(),
+            output_format: options.output_format,
+            dds_format: options.dds_format,
+            dds_quality: options.dds_quality,
+            mipmaps: options.mipmaps,
+            dds_rules: options.dds_rules.clone(),
+            webp: options.webp,
+        }
+    }
+
+    /// Copies the preset into `options`, settings a preset does not hold are left alone.
+    pub fn apply(&self, options: &mut ConvertOptions) {
+        options.output_format = self.output_format;
+        options.dds_format = self.dds_format;
+        options.dds_quality = self.dds_quality;
+        options.mipmaps = self.mipmaps;
+        options.dds_rules.clone_from(&self.dds_rules);
+        options.webp = self.webp;
+    }
+
+    /// Writes the preset as JSON when `path` ends with `.json`, as TOML otherwise.
+    pub fn export(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = if is_json(path) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            toml::to_string_pretty(self)?
+        };
+        fs::write(path, contents).with_context(|| format!("failed to write {:?}", path))
+    }
+
+    /// Reads a preset written by [`Preset::export`].
+    pub fn import(path: &Path) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))?;
+        let preset: Self = if is_json(path) {
+            serde_json::from_str(&contents)?
+        } else {
+            toml::from_str(&contents)?
+        };
+
+        if preset.name.trim().is_empty() {
+            bail!("preset in {:?} has no name", path);
+        }
+        Ok(preset)
+    }
+}
+
+/// Saved presets, kept sorted by name.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Presets {
+    #[serde(default, rename = "preset")]
+    presets: Vec<Preset>,
+}
+
+impl Presets {
+    /// `presets.toml` in the config folder of the user, `None` when there is no home folder.
+    pub fn default_path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "image converter rs")
+            .map(|dirs| dirs.config_dir().join("presets.toml"))
+    }
+
+    /// Loads the presets saved at `path`, no file means no presets yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))?;
+        let mut presets: Self = toml::from_str(&contents)?;
+        presets.presets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(presets)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {:?}", path))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Preset> {
+        self.presets.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Adds the preset, replacing the one with the same name.
+    pub fn insert(&mut self, preset: Preset) {
+        self.remove(&preset.name);
+        let index = self
+            .presets
+            .partition_point(|other| other.name < preset.name);
+        self.presets.insert(index, preset);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Preset> {
+        let index = self.presets.iter().position(|preset| preset.name == name)?;
+        Some(self.presets.remove(index))
+    }
+
+    /// Fails when there is no preset called `name` or `new_name` is already taken.
+    pub fn rename(&mut self, name: &str, new_name: &str) -> anyhow::Result<()> {
+        if new_name.trim().is_empty() {
+            bail!("preset name can't be empty");
+        }
+        if name != new_name && self.get(new_name).is_some() {
+            bail!("a preset called {:?} already exists", new_name);
+        }
+        let Some(mut preset) = self.remove(name) else {
+            bail!("no preset called {:?}", name);
+        };
+        preset.name = new_name.to_string();
+        self.insert(preset);
+        Ok(())
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normal_maps() -> Preset {
+        Preset {
+            name: "normal maps".to_string(),
+            output_format: ImageFormatEnum::DDS,
+            dds_format: image_dds::ImageFormat::BC7RgbaUnormSrgb,
+            dds_quality: image_dds::Quality::Slow,
+            mipmaps: MipmapPolicy::Fixed(4),
+            dds_rules: vec![DdsRule::new("_nrm", image_dds::ImageFormat::BC5RgUnorm)],
+            webp: WebpCompression::Lossy(90),
+        }
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let preset = normal_maps();
+
+        for path in ["./test_images/preset.toml", "./test_images/preset.json"] {
+            preset.export(Path::new(path)).unwrap();
+            assert_eq!(Preset::import(Path::new(path)).unwrap(), preset);
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_save_rename_and_delete() {
+        let path = Path::new("./test_images/presets_test/presets.toml");
+        let mut presets = Presets::default();
+        presets.insert(normal_maps());
+        presets.insert(Preset {
+            name: "albedo".to_string(),
+            ..Default::default()
+        });
+        presets.save(path).unwrap();
+
+        let mut presets = Presets::load(path).unwrap();
+        assert_eq!(
+            presets.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+            ["albedo", "normal maps"]
+        );
+
+        assert!(presets.rename("albedo", "normal maps").is_err());
+        presets.rename("albedo", "base color").unwrap();
+        assert!(presets.get("base color").is_some());
+
+        assert!(presets.remove("normal maps").is_some());
+        assert_eq!(presets.iter().count(), 1);
+
+        fs::remove_dir_all("./test_images/presets_test").unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::cancel::CancellationToken;
+use crate::report::{ConversionReport, FileReport};
+
+/// Events sent by [`convert_with_progress`](crate::convert_with_progress) while it runs.
+#[derive(Debug, Clone)]
+pub enum ConvertEvent {
+    /// Conversion began, `total` files will be reported.
+    Started { total: usize },
+    /// A file is being decoded. Several files are in flight when converting in parallel.
+    FileStarted { input: PathBuf },
+    /// A file was converted, skipped or failed.
+    FileFinished {
+        file: FileReport,
+        progress: Progress,
+    },
+    /// Every file was handled, same report as the one returned.
+    Finished(ConversionReport),
+}
+
+/// Snapshot of how far a conversion got.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+    /// Size of the input files handled so far.
+    pub bytes_processed: u64,
+    pub elapsed: Duration,
+    /// Estimated time left, based on the average time per completed file.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    /// Completed part of the conversion, from `0.0` to `1.0`.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.completed as f32 / self.total as f32
+    }
+
+    /// Input bytes handled per second.
+    pub fn bytes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.bytes_processed as f64 / seconds
+    }
+}
+
+/// Counts finished files, forwards events to the optional listener and holds the
+/// cancellation token of the run.
+/// Shared between the rayon workers, so every counter is atomic.
+pub(crate) struct ProgressTracker {
+    sender: Option<Sender<ConvertEvent>>,
+    cancel: CancellationToken,
+    start: Instant,
+    total: usize,
+    completed: AtomicUsize,
+    bytes_processed: AtomicU64,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(
+        sender: Option<Sender<ConvertEvent>>,
+        cancel: CancellationToken,
+        total: usize,
+    ) -> Self {
+        let tracker = Self {
+            sender,
+            cancel,
+            start: Instant::now(),
+            total,
+            completed: AtomicUsize::new(0),
+            bytes_processed: AtomicU64::new(0),
+        };
+        tracker.send(ConvertEvent::Started { total });
+        tracker
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub(crate) fn file_started(&self, input: &str) {
+        self.send(ConvertEvent::FileStarted {
+            input: PathBuf::from(input),
+        });
+    }
+
+    pub(crate) fn file_finished(&self, file: &FileReport) {
+        let completed = self.completed.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes_processed = self
+            .bytes_processed
+            .fetch_add(file.input_bytes, Ordering::Relaxed)
+            + file.input_bytes;
+
+        if self.sender.is_some() {
+            let progress = self.progress(completed, bytes_processed);
+            self.send(ConvertEvent::FileFinished {
+                file: file.clone(),
+                progress,
+            });
+        }
+    }
+
+    pub(crate) fn finished(&self, report: &ConversionReport) {
+        if self.sender.is_some() {
+            self.send(ConvertEvent::Finished(report.clone()));
+        }
+    }
+
+    fn progress(&self, completed: usize, bytes_processed: u64) -> Progress {
+        let elapsed = self.start.elapsed();
+        let eta = if completed == 0 {
+            None
+        } else {
+            let remaining = self.total.saturating_sub(completed) as u32;
+            Some(elapsed / completed as u32 * remaining)
+        };
+
+        Progress {
+            completed,
+            total: self.total,
+            bytes_processed,
+            elapsed,
+            eta,
+        }
+    }
+
+    fn send(&self, event: ConvertEvent) {
+        if let Some(sender) = &self.sender {
+            // the listener going away must not stop the conversion
+            if sender.send(event).is_err() {
+                debug!("progress listener disconnected");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::report::SkipReason;
+
+    #[test]
+    fn test_progress_events() {
+        let (tx, rx) = channel();
+        let tracker = ProgressTracker::new(Some(tx), CancellationToken::new(), 2);
+
+        tracker.file_started("a.png");
+        let mut file = FileReport::skipped("a.png", SkipReason::SameFormat);
+        file.input_bytes = 100;
+        tracker.file_finished(&file);
+        tracker.file_finished(&file);
+        drop(tracker);
+
+        let events: Vec<ConvertEvent> = rx.iter().collect();
+        assert!(matches!(events[0], ConvertEvent::Started { total: 2 }));
+        assert!(matches!(events[1], ConvertEvent::FileStarted { .. }));
+        let ConvertEvent::FileFinished { progress, .. } = &events[3] else {
+            panic!("expected file finished event");
+        };
+        assert_eq!(progress.completed, 2);
+        assert_eq!(progress.bytes_processed, 200);
+        assert_eq!(progress.fraction(), 1.0);
+        assert_eq!(progress.eta, Some(Duration::ZERO));
+    }
+}
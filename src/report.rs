@@ -0,0 +1,177 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Why an input file was not converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file already has the output format.
+    SameFormat,
+    /// The file does not exist anymore.
+    NotFound,
+    /// An earlier file failed and the conversion stopped, see [`ErrorPolicy::FailFast`](crate::ErrorPolicy::FailFast).
+    NotProcessed,
+    /// The conversion was cancelled before this file was started.
+    Cancelled,
+    /// The output file exists, see [`OverwritePolicy::SkipExisting`](crate::OverwritePolicy::SkipExisting).
+    OutputExists,
+    /// The output file is newer than the input, see [`OverwritePolicy::SkipIfNewer`](crate::OverwritePolicy::SkipIfNewer).
+    UpToDate,
+    /// Same content and settings as the last conversion, see [`CacheMode::Incremental`](crate::CacheMode::Incremental).
+    Unchanged,
+    /// Another input has the same output and is preferred, see [`CollisionPolicy::PreferFormat`](crate::CollisionPolicy::PreferFormat).
+    Collision,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::SameFormat => write!(f, "already in output format"),
+            SkipReason::NotFound => write!(f, "file not found"),
+            SkipReason::NotProcessed => write!(f, "not processed, an earlier file failed"),
+            SkipReason::Cancelled => write!(f, "cancelled"),
+            SkipReason::OutputExists => write!(f, "output already exists"),
+            SkipReason::UpToDate => write!(f, "output is up to date"),
+            SkipReason::Unchanged => write!(f, "unchanged since the last conversion"),
+            SkipReason::Collision => write!(f, "another input has the same output"),
+        }
+    }
+}
+
+/// What happened to a single input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Converted,
+    Skipped(SkipReason),
+    Failed,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileStatus::Converted => write!(f, "converted"),
+            FileStatus::Skipped(_) => write!(f, "skipped"),
+            FileStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// How the output file of a converted image was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputAction {
+    /// There was no file at the output path.
+    Created,
+    /// An existing file was replaced.
+    Overwritten,
+    /// The output path was taken, a numbered file name was used instead.
+    Renamed,
+}
+
+impl fmt::Display for OutputAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputAction::Created => write!(f, "created"),
+            OutputAction::Overwritten => write!(f, "overwritten"),
+            OutputAction::Renamed => write!(f, "renamed"),
+        }
+    }
+}
+
+/// Outcome of converting one input file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReport {
+    pub input: PathBuf,
+    /// Path the converted image is (or would have been) written to.
+    pub output: Option<PathBuf>,
+    /// Set once the output is written, see [`OverwritePolicy`](crate::OverwritePolicy).
+    pub output_action: Option<OutputAction>,
+    pub status: FileStatus,
+    /// Error message when the status is [`FileStatus::Failed`].
+    pub error: Option<String>,
+    /// Time spent decoding, encoding and writing this file.
+    pub duration: Duration,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+}
+
+impl FileReport {
+    pub(crate) fn skipped(input: impl Into<PathBuf>, reason: SkipReason) -> Self {
+        Self {
+            input: input.into(),
+            output: None,
+            output_action: None,
+            status: FileStatus::Skipped(reason),
+            error: None,
+            duration: Duration::ZERO,
+            input_bytes: 0,
+            output_bytes: 0,
+        }
+    }
+}
+
+/// Result of [`convert`](crate::convert), one entry per input file in the order of the inputs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionReport {
+    pub files: Vec<FileReport>,
+    /// Wall-clock time of the whole conversion.
+    pub duration: Duration,
+    /// The conversion was cancelled, files that were not started are skipped.
+    pub cancelled: bool,
+}
+
+impl ConversionReport {
+    pub fn converted_count(&self) -> usize {
+        self.count(|status| status == FileStatus::Converted)
+    }
+
+    pub fn skipped_count(&self) -> usize {
+        self.count(|status| matches!(status, FileStatus::Skipped(_)))
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.count(|status| status == FileStatus::Failed)
+    }
+
+    /// `true` when no file failed to convert.
+    pub fn is_success(&self) -> bool {
+        self.failed_count() == 0
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &FileReport> {
+        self.files
+            .iter()
+            .filter(|file| file.status == FileStatus::Failed)
+    }
+
+    pub fn input_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.input_bytes).sum()
+    }
+
+    pub fn output_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.output_bytes).sum()
+    }
+
+    fn count(&self, predicate: impl Fn(FileStatus) -> bool) -> usize {
+        self.files
+            .iter()
+            .filter(|file| predicate(file.status))
+            .count()
+    }
+}
+
+impl fmt::Display for ConversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} converted, {} skipped, {} failed in {:.2?}",
+            self.converted_count(),
+            self.skipped_count(),
+            self.failed_count(),
+            self.duration
+        )?;
+        if self.cancelled {
+            write!(f, " (cancelled)")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use globset::GlobBuilder;
+use image_dds::ImageFormat;
+use serde::{Deserialize, Serialize};
+
+use crate::format::{ConvertOptions, MipmapPolicy};
+
+/// DDS settings for the files whose name matches `pattern`, e.g. normal maps ending in `_nrm`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DdsRule {
+    /// Glob like `*_nrm.*`, matched against the whole path of the file.
+    /// A pattern without wildcards is a suffix of the file name without extension, e.g. `_nrm`.
+    /// Both are case insensitive.
+    pub pattern: String,
+    pub dds_format: ImageFormat,
+    pub quality: image_dds::Quality,
+    pub mipmaps: MipmapPolicy,
+    /// Use the sRGB variant of `dds_format` when there is one, the linear variant otherwise.
+    pub srgb: bool,
+}
+
+impl DdsRule {
+    pub fn new(pattern: impl Into<String>, dds_format: ImageFormat) -> Self {
+        Self {
+            pattern: pattern.into(),
+            dds_format,
+            quality: image_dds::Quality::Fast,
+            mipmaps: MipmapPolicy::default(),
+            srgb: is_srgb(dds_format),
+        }
+    }
+
+    /// Fails when `pattern` is not a valid glob.
+    pub fn matches(&self, path: &Path) -> anyhow::Result<bool> {
+        if !is_glob(&self.pattern) {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            return Ok(stem.ends_with(&self.pattern.to_lowercase()));
+        }
+
+        let matcher = GlobBuilder::new(&self.pattern)
+            .case_insensitive(true)
+            .build()?
+            .compile_matcher();
+        // rules are written with forward slashes on every platform
+        Ok(matcher.is_match(path.to_string_lossy().replace('\\', "/")))
+    }
+}
+
+/// DDS settings picked for one file.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct DdsSettings {
+    pub(crate) format: ImageFormat,
+    pub(crate) quality: image_dds::Quality,
+    pub(crate) mipmaps: MipmapPolicy,
+}
+
+impl ConvertOptions {
+    /// Settings of the first rule matching `path`, or the batch-wide settings when none does.
+    pub(crate) fn dds_settings(&self, path: &Path) -> anyhow::Result<DdsSettings> {
+        for rule in &self.dds_rules {
+            if rule.matches(path)? {
+                return Ok(DdsSettings {
+                    format: with_srgb(rule.dds_format, rule.srgb),
+                    quality: rule.quality,
+                    mipmaps: rule.mipmaps,
+                });
+            }
+        }
+
+        Ok(DdsSettings {
+            format: self.dds_format,
+            quality: self.dds_quality,
+            mipmaps: self.mipmaps,
+        })
+    }
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+pub(crate) fn is_srgb(format: ImageFormat) -> bool {
+    with_srgb(format, true) == format && with_srgb(format, false) != format
+}
+
+/// `format` with the requested color space, unchanged when it only exists in one.
+pub(crate) fn with_srgb(format: ImageFormat, srgb: bool) -> ImageFormat {
+    use ImageFormat::*;
+
+    let (linear, srgb_format) = match format {
+        Rgba8Unorm | Rgba8UnormSrgb => (Rgba8Unorm, Rgba8UnormSrgb),
+        Bgra8Unorm | Bgra8UnormSrgb => (Bgra8Unorm, Bgra8UnormSrgb),
+        BC1RgbaUnorm | BC1RgbaUnormSrgb => (BC1RgbaUnorm, BC1RgbaUnormSrgb),
+        BC2RgbaUnorm | BC2RgbaUnormSrgb => (BC2RgbaUnorm, BC2RgbaUnormSrgb),
+        BC3RgbaUnorm | BC3RgbaUnormSrgb => (BC3RgbaUnorm, BC3RgbaUnormSrgb),
+        BC7RgbaUnorm | BC7RgbaUnormSrgb => (BC7RgbaUnorm, BC7RgbaUnormSrgb),
+        other => return other,
+    };
+
+    if srgb {
+        srgb_format
+    } else {
+        linear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture_rules() -> ConvertOptions {
+        ConvertOptions {
+            dds_format: ImageFormat::BC1RgbaUnorm,
+            dds_rules: vec![
+                DdsRule::new("_nrm", ImageFormat::BC5RgUnorm),
+                DdsRule {
+                    srgb: true,
+                    mipmaps: MipmapPolicy::Disabled,
+                    ..DdsRule::new("**/albedo/*_base.*", ImageFormat::BC7RgbaUnorm)
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let options = texture_rules();
+
+        let normal = options
+            .dds_settings(Path::new("./textures/rock_NRM.png"))
+            .unwrap();
+        assert_eq!(normal.format, ImageFormat::BC5RgUnorm);
+
+        let albedo = options
+            .dds_settings(Path::new("C:\\textures\\albedo\\rock_base.tga"))
+            .unwrap();
+        assert_eq!(albedo.format, ImageFormat::BC7RgbaUnormSrgb);
+        assert_eq!(albedo.mipmaps, MipmapPolicy::Disabled);
+
+        let other = options
+            .dds_settings(Path::new("./textures/rock_spec.png"))
+            .unwrap();
+        assert_eq!(other.format, ImageFormat::BC1RgbaUnorm);
+    }
+
+    #[test]
+    fn test_invalid_rule_pattern() {
+        let options = ConvertOptions {
+            dds_rules: vec![DdsRule::new("*[_nrm.png", ImageFormat::BC5RgUnorm)],
+            ..Default::default()
+        };
+
+        assert!(options.dds_settings(Path::new("a_nrm.png")).is_err());
+    }
+
+    #[test]
+    fn test_with_srgb() {
+        assert_eq!(
+            with_srgb(ImageFormat::BC7RgbaUnormSrgb, false),
+            ImageFormat::BC7RgbaUnorm
+        );
+        assert_eq!(
+            with_srgb(ImageFormat::BC5RgUnorm, true),
+            ImageFormat::BC5RgUnorm
+        );
+        assert!(is_srgb(ImageFormat::BC3RgbaUnormSrgb));
+        assert!(!is_srgb(ImageFormat::BC4RUnorm));
+    }
+}
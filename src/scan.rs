@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::atomic::is_temp_file;
+use crate::cancel::CancellationToken;
+use crate::image_converter::has_image_extension;
+
+/// Gitignore-style patterns of files to leave out, read from every folder of the source tree.
+pub const IGNORE_FILE_NAME: &str = ".imageconverterignore";
+/// Images found by [`ImageScanner::scan_with_progress`] are sent at most this often.
+const SCAN_EVENT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sent by [`ImageScanner::scan_with_progress`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanEvent {
+    /// Images found since the last event, in walk order.
+    Found(Vec<String>),
+    /// Every image was sent, not sent when the scan was cancelled.
+    Finished,
+}
+
+/// Which files of the source folder are converted, see [`scan_image_files`].
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Globs like `textures/**` or `*_base.*`, matched against the path relative to the source
+    /// folder. Only matching files are converted, every image when empty.
+    pub include: Vec<String>,
+    /// Globs of files to leave out, checked after `include`.
+    pub exclude: Vec<String>,
+    /// Folder levels to go down, `Some(1)` only takes the images directly in the source folder.
+    pub max_depth: Option<usize>,
+    /// Also take files and folders whose name starts with a dot.
+    pub include_hidden: bool,
+    /// Take the images and folders symlinks point to instead of skipping symlinks.
+    pub follow_symlinks: bool,
+    /// Leave out the files matched by the [`IGNORE_FILE_NAME`] files of the source tree.
+    pub use_ignore_file: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: None,
+            include_hidden: false,
+            follow_symlinks: false,
+            use_ignore_file: true,
+        }
+    }
+}
+
+/// Walk `source_dir` recursively and collect every image, hidden ones included, skipping the
+/// files of the ignore files. Unlike the default [`ScanOptions`], which leave out hidden files.
+pub fn get_image_files(source_dir: &str) -> Vec<String> {
+    let options = ScanOptions {
+        include_hidden: true,
+        ..Default::default()
+    };
+    ImageScanner::new(source_dir, &options)
+        .expect("default scan options have no globs to fail")
+        .files()
+}
+
+/// Walk `source_dir` and collect the images `options` lets through.
+/// Fails when an include or exclude pattern is not a valid glob.
+pub fn scan_image_files(source_dir: &str, options: &ScanOptions) -> anyhow::Result<Vec<String>> {
+    Ok(ImageScanner::new(source_dir, options)?.files())
+}
+
+/// [`ScanOptions`] with the globs of one source folder parsed, so the walk itself can run on
+/// another thread.
+pub struct ImageScanner {
+    source_dir: PathBuf,
+    options: ScanOptions,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Ignore file of each folder, by its path relative to the source folder, read when first needed.
+    ignores: Mutex<HashMap<PathBuf, Gitignore>>,
+}
+
+impl ImageScanner {
+    /// Fails when an include or exclude pattern is not a valid glob.
+    pub fn new(source_dir: &str, options: &ScanOptions) -> anyhow::Result<Self> {
+        let include = if options.include.is_empty() {
+            None
+        } else {
+            Some(glob_set(&options.include)?)
+        };
+
+        Ok(Self {
+            source_dir: PathBuf::from(source_dir),
+            options: options.clone(),
+            include,
+            exclude: glob_set(&options.exclude)?,
+            ignores: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn files(&self) -> Vec<String> {
+        self.walk().collect()
+    }
+
+    /// Same as [`Self::files`], sending the images to `events` in batches while walking so
+    /// large or slow folders can be listed as they are read.
+    ///
+    /// Cancelling `cancel` or dropping the receiver stops the walk.
+    pub fn scan_with_progress(&self, events: Sender<ScanEvent>, cancel: &CancellationToken) {
+        let mut found = Vec::new();
+        let mut last_sent = Instant::now();
+        // every entry is looked at, so folders without images still stop and send in time
+        for file in self.walk_entries() {
+            if cancel.is_cancelled() {
+                debug!("scan of {:?} cancelled", self.source_dir);
+                return;
+            }
+            found.extend(file);
+            if !found.is_empty() && last_sent.elapsed() >= SCAN_EVENT_INTERVAL {
+                if events
+                    .send(ScanEvent::Found(std::mem::take(&mut found)))
+                    .is_err()
+                {
+                    return;
+                }
+                last_sent = Instant::now();
+            }
+        }
+
+        if cancel.is_cancelled() {
+            return;
+        }
+        if !found.is_empty() && events.send(ScanEvent::Found(found)).is_err() {
+            return;
+        }
+        // the listener may be gone already, nothing left to stop
+        let _ = events.send(ScanEvent::Finished);
+    }
+
+    fn walk(&self) -> impl Iterator<Item = String> + '_ {
+        self.walk_entries().flatten()
+    }
+
+    /// The path of every walked entry that is an image, `None` for the other entries.
+    fn walk_entries(&self) -> impl Iterator<Item = Option<String>> + '_ {
+        let mut walk = WalkDir::new(&self.source_dir).follow_links(self.options.follow_symlinks);
+        if let Some(max_depth) = self.options.max_depth {
+            walk = walk.max_depth(max_depth);
+        }
+
+        walk.into_iter()
+            // don't go into hidden or ignored folders at all
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !entry.file_type().is_dir() || self.keeps_dir(entry)
+            })
+            .map(|entry| {
+                let entry = entry.ok()?;
+                if !entry.file_type().is_file() || !self.accepts(entry.path()) {
+                    return None;
+                }
+                entry.path().to_str().map(String::from)
+            })
+    }
+
+    /// Whether the file at `path`, inside the source folder, is one [`Self::files`] would take.
+    pub(crate) fn accepts(&self, path: &Path) -> bool {
+        if !path.is_file() || !has_image_extension(path) || is_temp_file(path) {
+            return false;
+        }
+        if !self.options.follow_symlinks && path.is_symlink() {
+            return false;
+        }
+        let Some(relative) = self.relative_path(path) else {
+            return false;
+        };
+        let names: Vec<_> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+
+        if self
+            .options
+            .max_depth
+            .is_some_and(|max_depth| names.len() > max_depth)
+        {
+            return false;
+        }
+        if !self.options.include_hidden && names.iter().any(|name| name.starts_with('.')) {
+            return false;
+        }
+
+        // globs are written with forward slashes on every platform
+        let relative_glob_path = names.join("/");
+        if self
+            .include
+            .as_ref()
+            .is_some_and(|include| !include.is_match(&relative_glob_path))
+        {
+            return false;
+        }
+        !self.exclude.is_match(&relative_glob_path) && !self.is_ignored(&relative, false)
+    }
+
+    /// `path` relative to the source folder, also when only one of them is absolute
+    /// like the paths reported by the file watcher.
+    fn relative_path(&self, path: &Path) -> Option<PathBuf> {
+        if let Ok(relative) = path.strip_prefix(&self.source_dir) {
+            return Some(relative.to_path_buf());
+        }
+        let source_dir = self.source_dir.canonicalize().ok()?;
+        let path = path.canonicalize().ok()?;
+        Some(path.strip_prefix(source_dir).ok()?.to_path_buf())
+    }
+
+    fn keeps_dir(&self, entry: &DirEntry) -> bool {
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let ignored = self
+            .relative_path(entry.path())
+            .is_some_and(|relative| self.is_ignored(&relative, true));
+        (self.options.include_hidden || !hidden) && !ignored
+    }
+
+    /// Whether the ignore files of the folders above `relative` leave it out. Like with
+    /// `.gitignore`, the closest ignore file with a matching pattern decides.
+    fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if !self.options.use_ignore_file {
+            return false;
+        }
+        let mut ignores = self.ignores.lock().unwrap();
+        for dir in relative.ancestors().skip(1) {
+            let ignore = ignores
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| ignore_file(&self.source_dir.join(dir)));
+            let Ok(path) = relative.strip_prefix(dir) else {
+                continue;
+            };
+            match ignore.matched_path_or_any_parents(path, is_dir) {
+                Match::None => continue,
+                matched => return matched.is_ignore(),
+            }
+        }
+        false
+    }
+}
+
+fn glob_set(patterns: &[String]) -> anyhow::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(GlobBuilder::new(pattern).case_insensitive(true).build()?);
+    }
+    Ok(builder.build()?)
+}
+
+/// A broken line of the ignore file is skipped with a warning instead of failing the scan.
+fn ignore_file(dir: &Path) -> Gitignore {
+    let path = dir.join(IGNORE_FILE_NAME);
+    if !path.is_file() {
+        return Gitignore::empty();
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&path) {
+        warn!("invalid lines in {:?}: {}", path, e);
+    }
+    builder.build().unwrap_or_else(|e| {
+        warn!("ignoring {:?}: {}", path, e);
+        Gitignore::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::mpsc::channel;
+
+    use pathdiff::diff_paths;
+
+    use super::*;
+
+    #[test]
+    fn test_scan_options() {
+        let source_dir = "./test_images/scan_source";
+        for dir in ["textures/rock", ".hidden", "ignored", "textures/old"] {
+            fs::create_dir_all(Path::new(source_dir).join(dir)).unwrap();
+        }
+        for file in [
+            "top.png",
+            "top_nrm.png",
+            "textures/rock/rock.tga",
+            ".hidden/secret.png",
+            "ignored/old.png",
+            "textures/old/old.png",
+            "textures/rock/rock_old.png",
+        ] {
+            fs::copy(
+                "./test_images/o-a_base.png",
+                Path::new(source_dir).join(file),
+            )
+            .unwrap();
+        }
+        fs::write(Path::new(source_dir).join(IGNORE_FILE_NAME), "ignored/\n").unwrap();
+        // nested ignore files only match below their folder, the closest one decides
+        fs::write(
+            Path::new(source_dir)
+                .join("textures")
+                .join(IGNORE_FILE_NAME),
+            "/old/\n*_old.*\n",
+        )
+        .unwrap();
+        fs::write(
+            Path::new(source_dir)
+                .join("textures/rock")
+                .join(IGNORE_FILE_NAME),
+            "!rock_old.png\n",
+        )
+        .unwrap();
+
+        let scan = |options: ScanOptions| {
+            let mut files: Vec<String> = scan_image_files(source_dir, &options)
+                .unwrap()
+                .into_iter()
+                .map(|file| {
+                    diff_paths(&file, source_dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        let default = scan(ScanOptions::default());
+        let filtered = scan(ScanOptions {
+            include: vec!["*.png".to_string()],
+            exclude: vec!["*_NRM.*".to_string()],
+            ..Default::default()
+        });
+        let shallow = scan(ScanOptions {
+            max_depth: Some(1),
+            include_hidden: true,
+            use_ignore_file: false,
+            ..Default::default()
+        });
+        let everything = scan(ScanOptions {
+            include_hidden: true,
+            use_ignore_file: false,
+            ..Default::default()
+        });
+        let mut legacy = get_image_files(source_dir);
+        legacy.sort();
+        let invalid = scan_image_files(
+            source_dir,
+            &ScanOptions {
+                exclude: vec!["[".to_string()],
+                ..Default::default()
+            },
+        );
+        fs::remove_dir_all(source_dir).unwrap();
+
+        assert_eq!(
+            default,
+            [
+                "textures/rock/rock.tga",
+                "textures/rock/rock_old.png",
+                "top.png",
+                "top_nrm.png"
+            ]
+        );
+        assert_eq!(filtered, ["textures/rock/rock_old.png", "top.png"]);
+        assert_eq!(shallow, ["top.png", "top_nrm.png"]);
+        assert_eq!(everything.len(), 7);
+        // get_image_files keeps returning dot files like before the scan options
+        assert_eq!(legacy.len(), 5);
+        assert!(legacy.iter().any(|file| file.contains("secret.png")));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_scan_with_progress() {
+        let source_dir = "./test_images/scan_progress_source";
+        fs::create_dir_all(Path::new(source_dir).join("sub")).unwrap();
+        for file in ["a.png", "sub/b.png"] {
+            fs::copy(
+                "./test_images/o-a_base.png",
+                Path::new(source_dir).join(file),
+            )
+            .unwrap();
+        }
+
+        let scanner = ImageScanner::new(source_dir, &ScanOptions::default()).unwrap();
+        let (tx, rx) = channel();
+        scanner.scan_with_progress(tx, &CancellationToken::new());
+
+        let events: Vec<ScanEvent> = rx.iter().collect();
+        assert_eq!(events.last(), Some(&ScanEvent::Finished));
+        let found: Vec<String> = events
+            .into_iter()
+            .flat_map(|event| match event {
+                ScanEvent::Found(files) => files,
+                ScanEvent::Finished => Vec::new(),
+            })
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found, scanner.files());
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let (tx, rx) = channel();
+        scanner.scan_with_progress(tx, &cancel);
+        fs::remove_dir_all(source_dir).unwrap();
+        assert_eq!(rx.iter().count(), 0);
+    }
+}
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use log::{debug, error, info};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+use crate::format::ConvertOptions;
+use crate::image_converter::convert;
+use crate::report::ConversionReport;
+use crate::scan::{ImageScanner, ScanOptions};
+
+/// Wait used by the app and the command line before converting a file that was written.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Events sent by a [`FolderWatcher`].
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// Images were created or modified, they are converted next.
+    Changed(Vec<PathBuf>),
+    /// The changed images were converted.
+    Converted(ConversionReport),
+    /// Watching failed, e.g. the folder was removed.
+    Error(String),
+}
+
+/// Converts the images of a folder (recursively) whenever they are created or modified,
+/// until it is dropped.
+///
+/// Images are converted once no more writes happened for the debounce duration, so a file
+/// still being saved by another program is converted once. Outputs have the output format
+/// and are skipped, so the destination may be inside the watched folder. Only the images
+/// `scan` lets through are converted.
+pub struct FolderWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl FolderWatcher {
+    pub fn new(
+        source_dir: String,
+        output_path: String,
+        options: ConvertOptions,
+        scan: &ScanOptions,
+        debounce: Duration,
+        events: Sender<WatchEvent>,
+    ) -> anyhow::Result<Self> {
+        let scanner = ImageScanner::new(&source_dir, scan)?;
+        let watched_dir = std::fs::canonicalize(&source_dir)?;
+        let event_dir = watched_dir.clone();
+        let send = move |event: WatchEvent| {
+            if events.send(event).is_err() {
+                debug!("watch listener disconnected");
+            }
+        };
+
+        let mut debouncer = new_debouncer(debounce, move |result: DebounceEventResult| {
+            let changed = match result {
+                Ok(changed) => changed,
+                Err(e) => {
+                    error!("watch error: {:?}", e);
+                    send(WatchEvent::Error(e.to_string()));
+                    return;
+                }
+            };
+
+            let mut files: Vec<String> = changed
+                .iter()
+                // events are under the canonical folder, the outputs are found relative to `source_dir`
+                .filter_map(|event| event.path.strip_prefix(&event_dir).ok())
+                .map(|relative| Path::new(&source_dir).join(relative))
+                // deleted files are reported as well
+                .filter(|path| scanner.accepts(path))
+                .filter_map(|path| path.to_str().map(String::from))
+                .collect();
+            files.sort();
+            files.dedup();
+            if files.is_empty() {
+                return;
+            }
+
+            info!("{} images changed in {:?}", files.len(), source_dir);
+            send(WatchEvent::Changed(
+                files.iter().map(PathBuf::from).collect(),
+            ));
+            let report = convert(files, source_dir.clone(), output_path.clone(), &options);
+            send(WatchEvent::Converted(report));
+        })?;
+
+        debouncer
+            .watcher()
+            .watch(&watched_dir, RecursiveMode::Recursive)?;
+        info!("watching {:?}", watched_dir);
+
+        Ok(Self {
+            _debouncer: debouncer,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::mpsc::{channel, Receiver};
+
+    use super::*;
+    use crate::format::ImageFormatEnum;
+    use crate::report::FileStatus;
+
+    fn wait_for_conversion(rx: &Receiver<WatchEvent>) -> ConversionReport {
+        loop {
+            match rx.recv_timeout(Duration::from_secs(10)) {
+                Ok(WatchEvent::Converted(report)) => break report,
+                Ok(_) => continue,
+                Err(e) => panic!("no conversion after creating an image: {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_created_image() {
+        let source_dir = "./test_images/watch_source";
+        fs::create_dir_all(source_dir).unwrap();
+        let (tx, rx) = channel();
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            ..Default::default()
+        };
+        let watcher = FolderWatcher::new(
+            source_dir.to_string(),
+            source_dir.to_string(),
+            options,
+            &ScanOptions::default(),
+            Duration::from_millis(100),
+            tx,
+        )
+        .unwrap();
+
+        fs::copy(
+            "./test_images/o-a_base.png",
+            "./test_images/watch_source/o-a_base.png",
+        )
+        .unwrap();
+
+        let report = wait_for_conversion(&rx);
+        drop(watcher);
+
+        assert_eq!(report.files[0].status, FileStatus::Converted);
+        assert!(Path::new("./test_images/watch_source/o-a_base.tga").exists());
+
+        fs::remove_dir_all(source_dir).unwrap();
+    }
+    #[test]
+    fn test_convert_into_other_dest() {
+        let source_dir = "./test_images/watch_relative_source";
+        let output_path = "./test_images/watch_relative_output";
+        fs::create_dir_all(source_dir).unwrap();
+        let (tx, rx) = channel();
+        let options = ConvertOptions {
+            output_format: ImageFormatEnum::TGA,
+            ..Default::default()
+        };
+        let watcher = FolderWatcher::new(
+            source_dir.to_string(),
+            output_path.to_string(),
+            options,
+            &ScanOptions::default(),
+            Duration::from_millis(100),
+            tx,
+        )
+        .unwrap();
+
+        fs::copy(
+            "./test_images/o-a_base.png",
+            "./test_images/watch_relative_source/o-a_base.png",
+        )
+        .unwrap();
+
+        let report = wait_for_conversion(&rx);
+        drop(watcher);
+        let in_dest = Path::new("./test_images/watch_relative_output/o-a_base.tga").exists();
+        let in_source = Path::new("./test_images/watch_relative_source/o-a_base.tga").exists();
+        fs::remove_dir_all(source_dir).unwrap();
+        let _ = fs::remove_dir_all(output_path);
+
+        assert_eq!(report.files[0].status, FileStatus::Converted);
+        assert!(in_dest);
+        assert!(!in_source);
+    }
+}